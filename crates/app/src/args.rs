@@ -1,6 +1,9 @@
 use clap::{Parser, Subcommand};
 use std::path::PathBuf; // Import PathBuf
 
+/// Configuration file read when `--config` isn't given.
+pub const DEFAULT_CONFIG_FILE: &str = "./config.toml";
+
 /// Simple program to greet a person
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -9,22 +12,161 @@ pub struct Args {
     #[arg(
         short, 
         long, 
-        // Use PathBuf and set the default value to "./config.toml"
-        default_value = "./config.toml", 
-        long_help = "Path to the configuration file. Defaults to './config.toml'"
+        long_help = "Path to the configuration file, which must exist. Defaults to './config.toml', which is skipped if missing"
+    )]
+    pub config: Option<PathBuf>,
+
+    // 2. Global Option: Configuration overrides, applied after the file and `APP__SECTION__KEY` environment variables
+    #[arg(
+        short,
+        long = "set",
+        value_name = "KEY=VALUE",
+        global = true,
+        long_help = "Override a configuration value, e.g. `--set server.port=9000`. May be repeated."
     )]
-    pub config: PathBuf, // Changed from String to PathBuf
+    pub set: Vec<String>,
 
-    // 2. Subcommand: The action to perform
+    // 3. Subcommand: The action to perform
     #[command(subcommand)]
     pub command: Command,
 }
 
+impl Args {
+    /// The configuration file given with `--config`, or the default one.
+    pub fn config_file(&self) -> PathBuf {
+        self.config.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_FILE))
+    }
+}
+
 // Define the available subcommands (actions)
 #[derive(Subcommand, Debug)]
 pub enum Command {
     /// Starts the application server
-    Serve,
+    Serve {
+        /// Don't apply pending database migrations on startup, refuse to start while any are pending instead
+        #[arg(long)]
+        no_auto_migrate: bool,
+    },
+    /// Writes the OpenAPI document of the HTTP API, e.g. for client generation
+    Openapi {
+        /// File to write to. Prints to stdout when omitted.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Prints a random secret suitable for `security.auth_key`
+    GenerateSecret {
+        /// Number of random bytes, encoded as base64url.
+        #[arg(short, long, default_value_t = 48, value_parser = clap::value_parser!(u16).range(32..))]
+        bytes: u16,
+    },
+    /// Inspects the configuration without starting the server
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Manages user accounts without the HTTP API, e.g. to create the first admin or recover an account
+    User {
+        #[command(subcommand)]
+        command: UserCommand,
+    },
+    /// Manages the database schema of the configured `db.url`
+    Migrate {
+        /// Print the SQL that would run instead of running it
+        #[arg(long, global = true)]
+        dry_run: bool,
+        #[command(subcommand)]
+        command: MigrateCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Reports every unknown, mistyped or invalid value. Exits with status 1 if there are errors.
+    Check,
+    /// Prints the values set by the file, environment and command line, with their source
+    Print {
+        /// Also print values left at their default
+        #[arg(long)]
+        effective: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum UserCommand {
+    /// Creates a user and prints its ID
+    Create {
+        #[arg(long)]
+        username: String,
+        /// Display name, defaults to the username
+        #[arg(long)]
+        alias: Option<String>,
+        #[arg(long, default_value = "")]
+        email: String,
+        /// Create an admin instead of a regular user
+        #[arg(long)]
+        admin: bool,
+        /// Read the password from the first line of stdin instead of generating and printing one
+        #[arg(long)]
+        password_stdin: bool,
+    },
+    /// Lists users that are not deleted
+    List {
+        #[arg(long, default_value_t = 1)]
+        page: i64,
+        #[arg(long, default_value_t = 50)]
+        size: i64,
+    },
+    /// Sets a new password and signs the user out everywhere
+    SetPassword {
+        /// User ID or username
+        user: String,
+        /// Read the password from the first line of stdin instead of generating and printing one
+        #[arg(long)]
+        password_stdin: bool,
+    },
+    /// Makes a user an admin
+    Promote {
+        /// User ID or username
+        user: String,
+    },
+    /// Makes an admin a regular user
+    Demote {
+        /// User ID or username
+        user: String,
+    },
+    /// Deletes a user. It can be brought back with `user restore` until it is purged.
+    Delete {
+        /// User ID or username
+        user: String,
+    },
+    /// Restores a deleted user
+    Restore {
+        /// User ID, as printed by `user delete`
+        id: uuid::Uuid,
+    },
+    /// Permanently deletes a deleted user instead of waiting for `retention.deleted_user_days`
+    Purge {
+        /// User ID, as printed by `user delete`
+        id: uuid::Uuid,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MigrateCommand {
+    /// Lists every migration and whether it is applied
+    Status,
+    /// Applies pending migrations
+    Up {
+        /// Stop after this version instead of applying every pending migration
+        #[arg(long, value_name = "VERSION")]
+        to: Option<i64>,
+    },
+    /// Reverts the latest applied migration
+    Down {
+        /// Revert every migration newer than this version instead, `0` reverts all of them
+        #[arg(long, value_name = "VERSION")]
+        to: Option<i64>,
+    },
 }
 
 pub fn parse() -> Args {
@@ -1,79 +1,346 @@
 mod args;
 mod banner;
+mod reload;
+mod users;
 
-use db::db::{FullDb, sqlite_impl::SqliteDbImpl};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use db::db::{FullDb, memory_impl::InMemoryDbImpl, sqlite_impl::SqliteDbImpl};
 use service::CommonService;
+use std::path::Path;
 use std::process;
 use std::sync::Arc;
-use shared::config::Config;
+use rand::{RngCore, rngs::OsRng};
+use shared::{
+    config::{Config, ConfigHandle, ConfigLoader, ConfigSource, Severity},
+    models::user::UserType,
+};
 use tokio::runtime::Builder;
 use tracing::{error, info, warn};
+use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+use worker::retention::DeletedUserRetention;
 
 pub fn run() {
+    let args = args::parse();
+    match &args.command {
+        args::Command::Openapi { output } => return write_openapi(output.as_deref()),
+        args::Command::GenerateSecret { bytes } => return generate_secret(*bytes as usize),
+        args::Command::Config { command } => return config_command(&config_loader(&args), command),
+        args::Command::User { command } => return users::user_command(command_config(&config_loader(&args)), command),
+        args::Command::Migrate { dry_run, command } => return migrate_command(command_config(&config_loader(&args)), *dry_run, command),
+        args::Command::Serve { .. } => {}
+    }
+    let no_auto_migrate = matches!(args.command, args::Command::Serve { no_auto_migrate: true });
+
     banner::banner();
-    tracing_subscriber::fmt::init();
+    // Log at `info` until the configured `log.level` is known.
+    let (log_filter, log_handle) = tracing_subscriber::reload::Layer::new(EnvFilter::new("info"));
+    tracing_subscriber::registry().with(log_filter).with(tracing_subscriber::fmt::layer()).init();
     info!("Welcome to using template system..");
 
-    let args = args::parse();
-
-    let config: Config = if args.config.is_file() {
-        info!("Loading configuration from file: {:?}", args.config);
-        if let Ok(config) = Config::load_from_path(&args.config) {
-            config
-        } else {
-            warn!("Configuration file found but cannot be loaded or parsed. Using default config.");
-            Config::default()
+    let loader = config_loader(&args);
+    let loaded = match loader.load() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            error!("FATAL: Failed to load configuration: {}", e);
+            process::exit(1);
         }
-    } else {
-        warn!("Configuration file not found or path is not a file: {:?}. Using default config.", args.config);
-        Config::default()
     };
+    match &loaded.file {
+        Some(file) => info!("Loaded configuration file: {:?}", file),
+        None => warn!("No configuration file at {:?}. Using defaults and environment overrides.", args.config_file()),
+    }
+    for entry in loaded.entries() {
+        info!("  {}", entry);
+    }
+    for problem in &loaded.problems {
+        match problem.severity {
+            Severity::Warning => warn!("{}", problem),
+            Severity::Error => error!("{}", problem),
+        }
+    }
+    if loaded.has_errors() {
+        error!("FATAL: Invalid configuration. Run `config check` to list every problem.");
+        if loaded.problems.iter().any(|problem| problem.key == "security.auth_key") {
+            error!("Set `security.auth_key`, `APP__SECURITY__AUTH_KEY` or `security.auth_key_file` to a secret from `generate-secret`, or enable `server.dev_mode` for local development.");
+        }
+        process::exit(1);
+    }
+    match EnvFilter::try_new(&loaded.config.log.level) {
+        Ok(filter) => log_handle.reload(filter).expect("Failed to apply log level"),
+        Err(e) => {
+            error!("FATAL: error: `log.level` {} ({})", e, loaded.config.log.level);
+            process::exit(1);
+        }
+    }
+    let config = loaded.config.clone();
+    let config_handle = ConfigHandle::new(config.clone());
 
     info!("Configuration loaded successfully!");
 
     let rt = Builder::new_multi_thread().enable_all().build().expect("Failed to build tokio runtime");
 
     rt.block_on(async {
-        let storage: Arc<dyn FullDb> = {
-            let db_url = &config.db.url;
-
-            let scheme = db_url.split(':').next().unwrap_or("unknown");
-
-            match scheme {
-                "sqlite" => {
-                    info!("Initializing SQLite storage from URL: {}", db_url);
-
-                    match SqliteDbImpl::new(db_url.clone()).await {
-                        Ok(sqlite_storage) => Arc::new(sqlite_storage),
-                        Err(e) => {
-                            error!("FATAL: Failed to initialize SQLite storage: {}", e);
-                            process::exit(1);
-                        }
+        let storage = open_storage(&config.db.url).await;
+        if no_auto_migrate {
+            match storage.migration_status().await {
+                Ok(status) => {
+                    let pending = status.iter().filter(|migration| !migration.applied).count();
+                    if pending > 0 {
+                        error!("FATAL: {} database migration(s) pending and `--no-auto-migrate` is set. Apply them with `migrate up`.", pending);
+                        process::exit(1);
                     }
                 }
-                "postgres" | "mysql" | "mssql" => {
-                    error!(
-                        "FATAL: Database type '{}' is currently not supported. I plan to support it in a future release! Please use 'sqlite:' for now.",
-                        scheme
-                    );
+                Err(e) => {
+                    error!("FATAL: Failed to read database migration status: {}", e);
                     process::exit(1);
                 }
-                _ => {
-                    error!("FATAL: Unknown database scheme specified in config: {}", scheme);
+            }
+        } else {
+            match storage.migrate_up(None, false).await {
+                Ok(steps) => {
+                    for step in steps {
+                        info!("Applied database migration {} {}", step.version, step.description);
+                    }
+                }
+                Err(e) => {
+                    error!("FATAL: Failed to migrate database: {}", e);
                     process::exit(1);
                 }
             }
-        };
+        }
 
         let mut worker_factory = worker::WorkerFactory::new();
-        // worker_factory.push(SomeWorker::new(storage.clone()));
+        worker_factory.push(DeletedUserRetention::new(storage.clone(), config_handle.clone()));
         tokio::spawn(async move {
             worker_factory.run_all().await.expect("Workers run error!");
         });
 
-        let service = CommonService::new(storage, config.clone());
+        let reloader = reload::Reloader::new(loader, loaded, config_handle.clone(), log_handle);
+        tokio::spawn(reloader.run(args.config_file()));
+
+        let mut service = CommonService::new(storage.clone(), config_handle);
+        let bootstrapped = async { Ok::<_, db::Error>(storage.is_bootstrapped().await? || storage.exists_user_type(UserType::Admin).await?) };
+        match bootstrapped.await {
+            Ok(true) => {}
+            Ok(false) if config.security.bootstrap_token.is_some() => {
+                warn!("No admin exists yet. Create one by presenting `security.bootstrap_token` to POST /api/bootstrap, or with `user create --admin`.");
+            }
+            Ok(false) => {
+                let token = random_secret(32);
+                warn!("No admin exists yet. Create one by presenting this one-time bootstrap token to POST /api/bootstrap, or with `user create --admin`: {}", token);
+                service = service.with_bootstrap_token(token);
+            }
+            Err(e) => {
+                error!("FATAL: Failed to look up admins: {}", e);
+                process::exit(1);
+            }
+        }
 
         info!("Starting web server...");
-        web::serve(config, service).await;
+        web::serve(service).await;
     });
 }
+
+/// Connects to the storage selected by the scheme of `db_url`, exiting the process if that fails.
+async fn open_storage(db_url: &str) -> Arc<dyn FullDb> {
+    let scheme = db_url.split(':').next().unwrap_or("unknown");
+
+    match scheme {
+        "sqlite" => {
+            info!("Initializing SQLite storage from URL: {}", db_url);
+
+            match SqliteDbImpl::new(db_url.to_owned()).await {
+                Ok(sqlite_storage) => Arc::new(sqlite_storage),
+                Err(e) => {
+                    error!("FATAL: Failed to initialize SQLite storage: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "memory" => {
+            warn!("Using in-memory storage, all data will be lost when the server stops.");
+            Arc::new(InMemoryDbImpl::new())
+        }
+        #[cfg(feature = "postgres")]
+        "postgres" | "postgresql" => {
+            info!("Initializing PostgreSQL storage from URL: {}", db_url);
+
+            match db::db::postgres_impl::PostgresDbImpl::new(db_url.to_owned()).await {
+                Ok(postgres_storage) => Arc::new(postgres_storage),
+                Err(e) => {
+                    error!("FATAL: Failed to initialize PostgreSQL storage: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        #[cfg(not(feature = "postgres"))]
+        "postgres" | "postgresql" => {
+            error!("FATAL: Database type '{}' requires the `postgres` feature. Rebuild with `--features postgres`.", scheme);
+            process::exit(1);
+        }
+        #[cfg(feature = "mysql")]
+        "mysql" | "mariadb" => {
+            info!("Initializing MySQL storage from URL: {}", db_url);
+
+            match db::db::mysql_impl::MySqlDbImpl::new(db_url.to_owned()).await {
+                Ok(mysql_storage) => Arc::new(mysql_storage),
+                Err(e) => {
+                    error!("FATAL: Failed to initialize MySQL storage: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        #[cfg(not(feature = "mysql"))]
+        "mysql" | "mariadb" => {
+            error!("FATAL: Database type '{}' requires the `mysql` feature. Rebuild with `--features mysql`.", scheme);
+            process::exit(1);
+        }
+        "mssql" => {
+            error!(
+                "FATAL: Database type '{}' is currently not supported. I plan to support it in a future release! Please use 'sqlite:' for now.",
+                scheme
+            );
+            process::exit(1);
+        }
+        _ => {
+            error!("FATAL: Unknown database scheme specified in config: {}", scheme);
+            process::exit(1);
+        }
+    }
+}
+
+fn write_openapi(output: Option<&Path>) {
+    let json = match web::openapi().to_pretty_json() {
+        Ok(json) => json,
+        Err(e) => {
+            eprintln!("Failed to serialize the OpenAPI document: {e}");
+            process::exit(1);
+        }
+    };
+    match output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, json) {
+                eprintln!("Failed to write the OpenAPI document to {path:?}: {e}");
+                process::exit(1);
+            }
+        }
+        None => println!("{json}"),
+    }
+}
+
+fn config_loader(args: &args::Args) -> ConfigLoader {
+    let loader = match &args.config {
+        Some(file) => ConfigLoader::new().file(file),
+        None => ConfigLoader::new().default_file(args::DEFAULT_CONFIG_FILE),
+    };
+    loader.overrides(args.set.clone())
+}
+
+fn config_command(loader: &ConfigLoader, command: &args::ConfigCommand) {
+    let loaded = match loader.load() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("error: {e}");
+            process::exit(1);
+        }
+    };
+    match command {
+        args::ConfigCommand::Check => {
+            for problem in &loaded.problems {
+                eprintln!("{problem}");
+            }
+            if loaded.has_errors() {
+                process::exit(1);
+            }
+            match &loaded.file {
+                Some(file) => println!("Configuration OK ({}).", file.display()),
+                None => println!("Configuration OK (no configuration file, defaults and overrides only)."),
+            }
+        }
+        args::ConfigCommand::Print { effective } => {
+            // Dotted keys keep the output valid TOML.
+            for entry in loaded.entries().iter().filter(|entry| *effective || *entry.source != ConfigSource::Default) {
+                println!("{} = {}  # {}", entry.key, entry.value, entry.source);
+            }
+            for problem in &loaded.problems {
+                eprintln!("{problem}");
+            }
+        }
+    }
+}
+
+/// Loads the configuration of a one-off command, printing problems to stderr and exiting if there are errors.
+fn command_config(loader: &ConfigLoader) -> Config {
+    let loaded = match loader.load() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("error: {e}");
+            process::exit(1);
+        }
+    };
+    for problem in &loaded.problems {
+        eprintln!("{problem}");
+    }
+    if loaded.has_errors() {
+        process::exit(1);
+    }
+    // Storage errors are logged, keep them off stdout so the output of the command stays plain, e.g. SQL for `migrate --dry-run`.
+    tracing_subscriber::fmt().with_env_filter(EnvFilter::new("warn")).with_writer(std::io::stderr).init();
+    loaded.config
+}
+
+fn migrate_command(config: Config, dry_run: bool, command: &args::MigrateCommand) {
+    let rt = Builder::new_current_thread().enable_all().build().expect("Failed to build tokio runtime");
+    rt.block_on(async {
+        let storage = open_storage(&config.db.url).await;
+        let (steps, done, nothing) = match command {
+            args::MigrateCommand::Status => {
+                match storage.migration_status().await {
+                    Ok(status) if status.is_empty() => println!("This storage has no schema, there are no migrations."),
+                    Ok(status) => {
+                        for migration in status {
+                            let state = if migration.applied { "applied" } else { "pending" };
+                            let reversible = if migration.reversible { "" } else { " (irreversible)" };
+                            println!("{:<16} {:<8} {}{}", migration.version, state, migration.description, reversible);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("error: {e}");
+                        process::exit(1);
+                    }
+                }
+                return;
+            }
+            args::MigrateCommand::Up { to } => (storage.migrate_up(*to, dry_run).await, "Applied", "No pending migrations."),
+            args::MigrateCommand::Down { to } => (storage.migrate_down(*to, dry_run).await, "Reverted", "No applied migrations to revert."),
+        };
+        let steps = match steps {
+            Ok(steps) => steps,
+            Err(e) => {
+                eprintln!("error: {e}");
+                process::exit(1);
+            }
+        };
+        if steps.is_empty() {
+            println!("{nothing}");
+        }
+        for step in steps {
+            if dry_run {
+                println!("-- {} {}\n{}", step.version, step.description, step.sql.trim_end());
+            } else {
+                println!("{} {} {}", done, step.version, step.description);
+            }
+        }
+    });
+}
+
+fn generate_secret(bytes: usize) {
+    println!("{}", random_secret(bytes));
+}
+
+/// `bytes` random bytes from the OS, encoded as base64url.
+fn random_secret(bytes: usize) -> String {
+    let mut secret = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut secret);
+    URL_SAFE_NO_PAD.encode(secret)
+}
@@ -0,0 +1,139 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use shared::config::{ConfigHandle, ConfigLoader, LoadedConfig, Severity};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tracing::{error, info, warn};
+use tracing_subscriber::{EnvFilter, Registry, reload};
+
+pub type LogHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Quiet period after a file event before reloading, editors often write a file in several steps.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Reloads the configuration and swaps its runtime-safe values into the shared [`ConfigHandle`].
+pub struct Reloader {
+    loader: ConfigLoader,
+    current: LoadedConfig,
+    handle: ConfigHandle,
+    log: LogHandle,
+}
+
+impl Reloader {
+    pub fn new(loader: ConfigLoader, current: LoadedConfig, handle: ConfigHandle, log: LogHandle) -> Self {
+        Self { loader, current, handle, log }
+    }
+
+    /// Reloads whenever `file` changes and, on Unix, on SIGHUP. Runs until the process exits.
+    pub async fn run(mut self, file: PathBuf) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        // Dropping the watcher stops it.
+        let _watcher = watch(&file, tx);
+
+        #[cfg(unix)]
+        let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()).expect("failed to install SIGHUP handler");
+
+        loop {
+            #[cfg(unix)]
+            let hangup = hangup.recv();
+
+            #[cfg(not(unix))]
+            let hangup = std::future::pending::<Option<()>>();
+
+            let trigger = tokio::select! {
+                Some(()) = rx.recv() => {
+                    tokio::time::sleep(DEBOUNCE).await;
+                    while rx.try_recv().is_ok() {}
+                    "a change of the configuration file"
+                },
+                _ = hangup => "SIGHUP",
+            };
+            self.reload(trigger);
+        }
+    }
+
+    fn reload(&mut self, trigger: &str) {
+        info!("Reloading configuration after {}.", trigger);
+        let next = match self.loader.load() {
+            Ok(next) => next,
+            Err(e) => {
+                error!("Failed to reload configuration, keeping the current one: {}", e);
+                return;
+            }
+        };
+        for problem in &next.problems {
+            match problem.severity {
+                Severity::Warning => warn!("{}", problem),
+                Severity::Error => error!("{}", problem),
+            }
+        }
+        if next.has_errors() {
+            error!("Reloaded configuration is invalid, keeping the current one.");
+            return;
+        }
+        let filter = match EnvFilter::try_new(&next.config.log.level) {
+            Ok(filter) => filter,
+            Err(e) => {
+                error!("error: `log.level` {} ({}), keeping the current configuration.", e, next.config.log.level);
+                return;
+            }
+        };
+
+        let (reloaded, changes) = match self.current.reload(&next) {
+            Ok(reloaded) => reloaded,
+            Err(e) => {
+                error!("Failed to apply reloaded configuration, keeping the current one: {}", e);
+                return;
+            }
+        };
+        if changes.is_empty() {
+            info!("Configuration unchanged.");
+            return;
+        }
+        for change in &changes {
+            if change.applied {
+                info!("  {}", change);
+            } else {
+                warn!("  {}", change);
+            }
+        }
+
+        if !changes.iter().any(|change| change.applied) {
+            info!("Nothing to apply without a restart.");
+            return;
+        }
+
+        if changes.iter().any(|change| change.applied && change.key == "log.level")
+            && let Err(e) = self.log.reload(filter)
+        {
+            error!("Failed to apply `log.level`: {}", e);
+        }
+        self.handle.set(reloaded.config.clone());
+        self.current = reloaded;
+        info!("Configuration reloaded.");
+    }
+}
+
+/// Watches the directory of `file`, so editors that save by replacing the file are noticed too.
+fn watch(file: &Path, tx: UnboundedSender<()>) -> Option<RecommendedWatcher> {
+    let dir = file.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let name = file.file_name()?.to_owned();
+    let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event
+            && !event.kind.is_access()
+            && event.paths.iter().any(|path| path.file_name() == Some(name.as_os_str()))
+        {
+            let _ = tx.send(());
+        }
+    });
+    match watcher.and_then(|mut watcher| watcher.watch(dir, RecursiveMode::NonRecursive).map(|()| watcher)) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            warn!("Cannot watch {:?} for changes, reload with SIGHUP instead: {}", file, e);
+            None
+        }
+    }
+}
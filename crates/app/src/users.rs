@@ -0,0 +1,137 @@
+use std::{io::BufRead, process};
+
+use service::{CommonService, CoreService, service_ext::user_ext::UserExt};
+use shared::{
+    config::{Config, ConfigHandle},
+    models::{
+        Pagination,
+        user::{UserDetail, UserDetailToAddOrUpdate, UserFilter, UserSort, UserType},
+    },
+};
+use tokio::runtime::Builder;
+use uuid::Uuid;
+
+use crate::args::UserCommand;
+
+/// Length in bytes of generated passwords, 24 characters once encoded.
+const GENERATED_PASSWORD_BYTES: usize = 18;
+
+/// Runs a `user` command through the service layer as the system identity.
+pub fn user_command(config: Config, command: &UserCommand) {
+    let rt = Builder::new_current_thread().enable_all().build().expect("Failed to build tokio runtime");
+    rt.block_on(async {
+        let storage = crate::open_storage(&config.db.url).await;
+        match storage.migration_status().await {
+            Ok(status) if status.iter().any(|migration| !migration.applied) => fail("the database has pending migrations, apply them with `migrate up` first"),
+            Ok(_) => {}
+            Err(e) => fail(e),
+        }
+        let system = CommonService::new(storage, ConfigHandle::new(config)).system();
+
+        match command {
+            UserCommand::Create {
+                username,
+                alias,
+                email,
+                admin,
+                password_stdin,
+            } => {
+                let (password, generated) = password(*password_stdin);
+                let detail = UserDetailToAddOrUpdate {
+                    alias: alias.clone().unwrap_or_else(|| username.clone()),
+                    username: username.clone(),
+                    password: password.clone(),
+                    email: email.clone(),
+                };
+                let user_type = if *admin { UserType::Admin } else { UserType::Regular };
+                let id = system.create_user(user_type, detail).await.unwrap_or_else(|e| fail(e));
+                println!("Created {:?} user {} with ID {}.", user_type, username, id);
+                if generated {
+                    println!("Password: {}", password);
+                }
+            }
+            UserCommand::List { page, size } => {
+                let users = system.get_user_list(Pagination::new(*page, *size), UserSort::default(), UserFilter::default()).await.unwrap_or_else(|e| fail(e));
+                println!("{:<36}  {:<7}  {:<24}  {:<24}  EMAIL", "ID", "TYPE", "USERNAME", "ALIAS");
+                for user in &users.items {
+                    println!("{:<36}  {:<7}  {:<24}  {:<24}  {}", user.id, format!("{:?}", user.user_type), user.username, user.alias, user.email);
+                }
+                println!("{} of {} users{}.", users.items.len(), users.total, if users.has_next { ", see the next `--page`" } else { "" });
+            }
+            UserCommand::SetPassword { user, password_stdin } => {
+                let user = find(&system, user).await;
+                let (password, generated) = password(*password_stdin);
+                if !system.set_user_password(user.id, &password).await.unwrap_or_else(|e| fail(e)) {
+                    fail(format!("user {} no longer exists", user.id));
+                }
+                println!("Password of {} changed, all of their sessions are signed out.", user.username);
+                if generated {
+                    println!("Password: {}", password);
+                }
+            }
+            UserCommand::Promote { user } => set_user_type(&system, user, UserType::Admin).await,
+            UserCommand::Demote { user } => set_user_type(&system, user, UserType::Regular).await,
+            UserCommand::Delete { user } => {
+                let user = find(&system, user).await;
+                if !system.remove_user(user.id).await.unwrap_or_else(|e| fail(e)) {
+                    fail(format!("user {} no longer exists", user.id));
+                }
+                println!("Deleted {} ({}). Restore with `user restore {}`.", user.username, user.id, user.id);
+            }
+            UserCommand::Restore { id } => {
+                if !system.restore_user(*id).await.unwrap_or_else(|e| fail(e)) {
+                    fail(format!("no deleted user with ID {id}"));
+                }
+                println!("Restored user {}.", id);
+            }
+            UserCommand::Purge { id } => {
+                if !system.purge_user(*id).await.unwrap_or_else(|e| fail(e)) {
+                    fail(format!("no deleted user with ID {id}"));
+                }
+                println!("Purged user {}, this can't be undone.", id);
+            }
+        }
+    });
+}
+
+async fn set_user_type(system: &CoreService, user: &str, user_type: UserType) {
+    let user = find(system, user).await;
+    if user.user_type == user_type {
+        println!("{} already is {:?}.", user.username, user_type);
+        return;
+    }
+    if !system.set_user_type(user.id, user_type).await.unwrap_or_else(|e| fail(e)) {
+        fail(format!("user {} no longer exists", user.id));
+    }
+    println!("{} is now {:?}, their current sessions must login again.", user.username, user_type);
+}
+
+/// Looks a live user up by ID or, if `user` isn't one, by username.
+async fn find(system: &CoreService, user: &str) -> UserDetail {
+    let found = match user.parse::<Uuid>() {
+        Ok(id) => system.get_user(id).await,
+        Err(_) => system.get_user_by_username(user).await,
+    };
+    match found {
+        Ok(Some(detail)) => detail,
+        Ok(None) => fail(format!("no user `{user}`")),
+        Err(e) => fail(e),
+    }
+}
+
+/// The password from stdin, or a generated one. Returns whether it was generated.
+fn password(from_stdin: bool) -> (String, bool) {
+    if !from_stdin {
+        return (crate::random_secret(GENERATED_PASSWORD_BYTES), true);
+    }
+    let mut line = String::new();
+    if let Err(e) = std::io::stdin().lock().read_line(&mut line) {
+        fail(format!("failed to read the password from stdin: {e}"));
+    }
+    (line.trim_end_matches(['\r', '\n']).to_owned(), false)
+}
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("error: {message}");
+    process::exit(1);
+}
@@ -0,0 +1,19 @@
+--- Users
+--- `utf8mb4_bin` keeps username lookups case-sensitive like the other backends.
+CREATE TABLE IF NOT EXISTS users (
+    id BINARY(16) NOT NULL PRIMARY KEY,
+    alias VARCHAR(255) NOT NULL,
+    username VARCHAR(255) NOT NULL,
+    password VARCHAR(255) NOT NULL,
+    email VARCHAR(255) NOT NULL DEFAULT '',
+    user_type SMALLINT NOT NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    is_deleted BOOLEAN NOT NULL DEFAULT FALSE,
+    INDEX idx_users_username (username),
+    INDEX idx_users_user_type (user_type),
+    INDEX idx_users_alias (alias),
+    INDEX idx_users_email (email),
+    INDEX idx_users_is_deleted (is_deleted)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+---
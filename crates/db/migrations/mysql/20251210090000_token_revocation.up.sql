@@ -0,0 +1,9 @@
+--- Users: access tokens issued at or before this time are rejected
+ALTER TABLE users ADD COLUMN tokens_valid_after DATETIME(6) NULL;
+--- Revoked access tokens, kept until they would have expired anyway
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+    jti BINARY(16) NOT NULL PRIMARY KEY,
+    expires_at DATETIME(6) NOT NULL,
+    INDEX idx_revoked_tokens_expires_at (expires_at)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+---
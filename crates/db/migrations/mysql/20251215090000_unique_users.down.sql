@@ -0,0 +1,7 @@
+--- Users
+ALTER TABLE users
+    DROP INDEX idx_users_email_unique,
+    DROP INDEX idx_users_username_unique,
+    DROP COLUMN live_email,
+    DROP COLUMN live_username;
+---
@@ -0,0 +1,9 @@
+--- Users: usernames and, ignoring case, non-empty emails are unique among live users.
+--- Fails if live users already share one, resolve those first.
+--- MySQL has no partial indexes, the generated columns are NULL for rows that must not conflict.
+ALTER TABLE users
+    ADD COLUMN live_username VARCHAR(255) GENERATED ALWAYS AS (IF(is_deleted, NULL, username)) VIRTUAL,
+    ADD COLUMN live_email VARCHAR(255) GENERATED ALWAYS AS (IF(is_deleted OR email = '', NULL, LOWER(email))) VIRTUAL,
+    ADD UNIQUE INDEX idx_users_username_unique (live_username),
+    ADD UNIQUE INDEX idx_users_email_unique (live_email);
+---
@@ -0,0 +1,8 @@
+--- Users: when a user was deleted, the retention worker purges them once this is old enough.
+--- Users deleted before this migration count as deleted now.
+--- Nothing is purged unless `retention.deleted_user_days` is set, it defaults to 0 (disabled).
+ALTER TABLE users
+    ADD COLUMN deleted_at DATETIME(6) NULL,
+    ADD INDEX idx_users_deleted_at (deleted_at);
+UPDATE users SET deleted_at = CURRENT_TIMESTAMP(6) WHERE is_deleted = TRUE;
+---
@@ -0,0 +1,3 @@
+--- Users
+ALTER TABLE users DROP COLUMN version;
+---
@@ -0,0 +1,9 @@
+--- Bootstrap: a single row, written together with the first admin. Once it exists POST /api/bootstrap is refused,
+--- even if every admin is deleted later. Databases that already had an admin count as bootstrapped.
+CREATE TABLE IF NOT EXISTS bootstrap (
+    id SMALLINT NOT NULL PRIMARY KEY CHECK (id = 1),
+    bootstrapped_at DATETIME(6) NOT NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+INSERT INTO bootstrap (id, bootstrapped_at)
+SELECT 1, CURRENT_TIMESTAMP(6) FROM DUAL WHERE EXISTS (SELECT 1 FROM users WHERE user_type = 0);
+---
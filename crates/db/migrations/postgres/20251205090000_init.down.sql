@@ -0,0 +1,3 @@
+--- Users
+DROP TABLE IF EXISTS users;
+---
@@ -0,0 +1,7 @@
+--- User roles
+DROP TABLE IF EXISTS user_roles;
+--- Role permissions
+DROP TABLE IF EXISTS role_permissions;
+--- Roles
+DROP TABLE IF EXISTS roles;
+---
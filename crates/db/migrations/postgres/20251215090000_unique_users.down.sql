@@ -0,0 +1,4 @@
+--- Users
+DROP INDEX IF EXISTS idx_users_email_unique;
+DROP INDEX IF EXISTS idx_users_username_unique;
+---
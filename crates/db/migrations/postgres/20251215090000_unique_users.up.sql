@@ -0,0 +1,5 @@
+--- Users: usernames and, ignoring case, non-empty emails are unique among live users.
+--- Fails if live users already share one, resolve those first.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_username_unique ON users (username) WHERE is_deleted = FALSE;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_email_unique ON users (lower(email)) WHERE is_deleted = FALSE AND email <> '';
+---
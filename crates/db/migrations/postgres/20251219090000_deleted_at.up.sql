@@ -0,0 +1,7 @@
+--- Users: when a user was deleted, the retention worker purges them once this is old enough.
+--- Users deleted before this migration count as deleted now.
+--- Nothing is purged unless `retention.deleted_user_days` is set, it defaults to 0 (disabled).
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+UPDATE users SET deleted_at = now() WHERE is_deleted = TRUE;
+CREATE INDEX IF NOT EXISTS idx_users_deleted_at ON users (deleted_at);
+---
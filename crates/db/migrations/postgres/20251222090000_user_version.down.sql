@@ -0,0 +1,3 @@
+--- Users
+ALTER TABLE users DROP COLUMN IF EXISTS version;
+---
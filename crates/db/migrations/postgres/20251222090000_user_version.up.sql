@@ -0,0 +1,3 @@
+--- Users: incremented by every change, the ETag of a user.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 1;
+---
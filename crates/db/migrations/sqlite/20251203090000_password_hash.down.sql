@@ -0,0 +1,4 @@
+--- Users: back to looking up by username and password
+DROP INDEX IF EXISTS idx_users_username;
+CREATE INDEX IF NOT EXISTS idx_users_username_password ON users (username, password);
+---
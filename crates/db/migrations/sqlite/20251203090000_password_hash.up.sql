@@ -0,0 +1,5 @@
+--- Users: passwords are stored as Argon2 PHC strings and verified in the service layer,
+--- so lookups go by username only.
+DROP INDEX IF EXISTS idx_users_username_password;
+CREATE INDEX IF NOT EXISTS idx_users_username ON users (username);
+---
@@ -0,0 +1,3 @@
+--- Refresh tokens
+DROP TABLE IF EXISTS refresh_tokens;
+---
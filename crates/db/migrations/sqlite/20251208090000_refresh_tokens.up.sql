@@ -0,0 +1,16 @@
+--- Refresh tokens
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    family_id TEXT NOT NULL,
+    token_hash TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    used_at TEXT,
+    revoked BOOLEAN NOT NULL DEFAULT false,
+    created_at TEXT NOT NULL DEFAULT (strftime ('%Y-%m-%dT%H:%M:%SZ', 'now', 'utc'))
+);
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_id ON refresh_tokens (id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_refresh_tokens_token_hash ON refresh_tokens (token_hash);
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_family_id ON refresh_tokens (family_id);
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_user_id ON refresh_tokens (user_id);
+---
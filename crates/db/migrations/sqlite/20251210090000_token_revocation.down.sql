@@ -0,0 +1,5 @@
+--- Revoked access tokens
+DROP TABLE IF EXISTS revoked_tokens;
+--- Users
+ALTER TABLE users DROP COLUMN tokens_valid_after;
+---
@@ -0,0 +1,10 @@
+--- Users: access tokens issued at or before this time are rejected
+ALTER TABLE users ADD COLUMN tokens_valid_after TEXT;
+--- Revoked access tokens, kept until they would have expired anyway
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+    jti TEXT NOT NULL,
+    expires_at TEXT NOT NULL
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_revoked_tokens_jti ON revoked_tokens (jti);
+CREATE INDEX IF NOT EXISTS idx_revoked_tokens_expires_at ON revoked_tokens (expires_at);
+---
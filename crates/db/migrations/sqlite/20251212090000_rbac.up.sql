@@ -0,0 +1,24 @@
+--- Roles
+CREATE TABLE IF NOT EXISTS roles (
+    id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    created_at TEXT NOT NULL DEFAULT (strftime ('%Y-%m-%dT%H:%M:%SZ', 'now', 'utc')),
+    updated_at TEXT NOT NULL DEFAULT (strftime ('%Y-%m-%dT%H:%M:%SZ', 'now', 'utc'))
+);
+CREATE INDEX IF NOT EXISTS idx_roles_id ON roles (id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_roles_name ON roles (name);
+--- Role permissions, stored as their string form, e.g. `users.read`
+CREATE TABLE IF NOT EXISTS role_permissions (
+    role_id TEXT NOT NULL,
+    permission TEXT NOT NULL
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_role_permissions_role_id_permission ON role_permissions (role_id, permission);
+--- User roles
+CREATE TABLE IF NOT EXISTS user_roles (
+    user_id TEXT NOT NULL,
+    role_id TEXT NOT NULL
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_roles_user_id_role_id ON user_roles (user_id, role_id);
+CREATE INDEX IF NOT EXISTS idx_user_roles_role_id ON user_roles (role_id);
+---
@@ -0,0 +1,16 @@
+--- User search: trigram index of alias, username and email, so any substring of three or more characters matches.
+--- It keeps its own copy of the text, an external content table would break on VACUUM as `users` has no INTEGER PRIMARY KEY.
+CREATE VIRTUAL TABLE IF NOT EXISTS users_fts USING fts5 (id UNINDEXED, alias, username, email, tokenize = 'trigram');
+INSERT INTO users_fts (id, alias, username, email) SELECT id, alias, username, email FROM users;
+--- Kept in sync by triggers
+CREATE TRIGGER IF NOT EXISTS users_fts_insert AFTER INSERT ON users BEGIN
+    INSERT INTO users_fts (id, alias, username, email) VALUES (new.id, new.alias, new.username, new.email);
+END;
+CREATE TRIGGER IF NOT EXISTS users_fts_update AFTER UPDATE OF id, alias, username, email ON users BEGIN
+    DELETE FROM users_fts WHERE id = old.id;
+    INSERT INTO users_fts (id, alias, username, email) VALUES (new.id, new.alias, new.username, new.email);
+END;
+CREATE TRIGGER IF NOT EXISTS users_fts_delete AFTER DELETE ON users BEGIN
+    DELETE FROM users_fts WHERE id = old.id;
+END;
+---
@@ -0,0 +1,7 @@
+--- Users: when a user was deleted, the retention worker purges them once this is old enough.
+--- Users deleted before this migration count as deleted now.
+--- Nothing is purged unless `retention.deleted_user_days` is set, it defaults to 0 (disabled).
+ALTER TABLE users ADD COLUMN deleted_at TEXT;
+UPDATE users SET deleted_at = strftime ('%Y-%m-%dT%H:%M:%SZ', 'now', 'utc') WHERE is_deleted = 1;
+CREATE INDEX IF NOT EXISTS idx_users_deleted_at ON users (deleted_at);
+---
@@ -0,0 +1,3 @@
+--- Users: repaired `updated_at` values are kept.
+ALTER TABLE users DROP COLUMN version;
+---
@@ -0,0 +1,7 @@
+--- Users: incremented by every change, the ETag of a user.
+ALTER TABLE users ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+--- Users: the default of `updated_at` in the init migration repeats the minutes instead of the seconds.
+--- Rows still holding that default were never updated, so they were last changed when created.
+UPDATE users SET updated_at = created_at
+WHERE length(updated_at) = 20 AND substr(updated_at, 15, 2) = substr(updated_at, 18, 2) AND substr(updated_at, 1, 16) = substr(created_at, 1, 16);
+---
@@ -0,0 +1,3 @@
+--- Times: normalized values are kept, they read back the same.
+SELECT 1;
+---
@@ -0,0 +1,17 @@
+--- Times: stored as `strftime('%Y-%m-%dT%H:%M:%fZ')`, e.g. `2025-12-24T09:00:00.000Z`, so comparing them as text orders them.
+--- Rows written so far mix that with the column defaults' `%SZ` and RFC 3339 with `+00:00` and nanoseconds.
+--- The defaults keep their old format, every insert binds its times instead.
+UPDATE users SET
+    created_at = COALESCE(strftime ('%Y-%m-%dT%H:%M:%fZ', created_at), created_at),
+    updated_at = COALESCE(strftime ('%Y-%m-%dT%H:%M:%fZ', updated_at), updated_at),
+    tokens_valid_after = COALESCE(strftime ('%Y-%m-%dT%H:%M:%fZ', tokens_valid_after), tokens_valid_after),
+    deleted_at = COALESCE(strftime ('%Y-%m-%dT%H:%M:%fZ', deleted_at), deleted_at);
+UPDATE roles SET
+    created_at = COALESCE(strftime ('%Y-%m-%dT%H:%M:%fZ', created_at), created_at),
+    updated_at = COALESCE(strftime ('%Y-%m-%dT%H:%M:%fZ', updated_at), updated_at);
+UPDATE refresh_tokens SET
+    expires_at = COALESCE(strftime ('%Y-%m-%dT%H:%M:%fZ', expires_at), expires_at),
+    used_at = COALESCE(strftime ('%Y-%m-%dT%H:%M:%fZ', used_at), used_at),
+    created_at = COALESCE(strftime ('%Y-%m-%dT%H:%M:%fZ', created_at), created_at);
+UPDATE revoked_tokens SET expires_at = COALESCE(strftime ('%Y-%m-%dT%H:%M:%fZ', expires_at), expires_at);
+---
@@ -0,0 +1,3 @@
+--- Bootstrap
+DROP TABLE IF EXISTS bootstrap;
+---
@@ -0,0 +1,46 @@
+use std::ops::{Deref, DerefMut};
+
+use sqlx::{Database, Pool, Transaction, pool::PoolConnection};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::Result;
+
+/// The transaction every statement of a handle from [`FullDb::begin`](crate::db::FullDb::begin) runs in.
+pub(crate) type SharedTransaction<DB> = Mutex<Transaction<'static, DB>>;
+
+/// The connection a single statement runs on, a pooled one or the one of the handle's transaction.
+///
+/// Keep it to one statement. Held across another call of the same transactional handle, it deadlocks.
+pub(crate) enum Conn<'a, DB: Database> {
+    Pool(PoolConnection<DB>),
+    Transaction(MutexGuard<'a, Transaction<'static, DB>>),
+}
+
+impl<'a, DB: Database> Conn<'a, DB> {
+    pub(crate) async fn acquire(pool: &Pool<DB>, transaction: &'a Option<SharedTransaction<DB>>) -> Result<Self> {
+        Ok(match transaction {
+            Some(transaction) => Conn::Transaction(transaction.lock().await),
+            None => Conn::Pool(pool.acquire().await?),
+        })
+    }
+}
+
+impl<DB: Database> Deref for Conn<'_, DB> {
+    type Target = DB::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Conn::Pool(conn) => conn,
+            Conn::Transaction(transaction) => transaction,
+        }
+    }
+}
+
+impl<DB: Database> DerefMut for Conn<'_, DB> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Conn::Pool(conn) => conn,
+            Conn::Transaction(transaction) => transaction,
+        }
+    }
+}
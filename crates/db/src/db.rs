@@ -1,16 +1,41 @@
 pub mod sqlite_impl;
+pub mod memory_impl;
+#[cfg(feature = "postgres")]
+pub mod postgres_impl;
+#[cfg(feature = "mysql")]
+pub mod mysql_impl;
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+pub mod sql_impl;
 pub mod any_impl;
 
-use crate::Result;
+use crate::{
+    Result,
+    migrate::{MigrationStatus, MigrationStep},
+};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use shared::models::{
-    Pagination,
-    user::{UserDetail, UserDetailToAddOrUpdate, UserType},
+    Page, Pagination,
+    permission::{Permission, Role, RoleToAddOrUpdate},
+    token::RefreshToken,
+    user::{UserDetail, UserDetailToAddOrUpdate, UserDetailToUpdate, UserFilter, UserPatch, UserSearchHit, UserSort, UserType},
 };
 use uuid::Uuid;
 
-pub trait FullDb: UserDb {}
-impl<T> FullDb for T where T: UserDb {}
+#[async_trait]
+pub trait FullDb: UserDb + TokenDb + PermissionDb + MigrateDb {
+    /// Starts a transaction. Calls through the returned handle only take effect once it is committed.
+    async fn begin(&self) -> Result<Box<dyn DbTransaction>>;
+}
+
+/// Storage calls that take effect together or not at all, started by [`FullDb::begin`].
+///
+/// Dropping the handle without committing rolls it back, so an early return with `?` undoes every call made through it.
+#[async_trait]
+pub trait DbTransaction: UserDb + TokenDb + PermissionDb {
+    async fn commit(self: Box<Self>) -> Result<()>;
+    async fn rollback(self: Box<Self>) -> Result<()>;
+}
 
 #[async_trait]
 pub trait UserDb: Send + Sync {
@@ -18,7 +43,80 @@ pub trait UserDb: Send + Sync {
     async fn exists_user_type(&self, user_type: UserType) -> Result<bool>;
     async fn remove_user(&self, id: Uuid) -> Result<bool>;
     async fn get_user(&self, id: Uuid) -> Result<Option<UserDetail>>;
-    async fn get_user_list(&self, pagination: Pagination) -> Result<Vec<UserDetail>>;
-    async fn get_user_by_validate(&self, username: &str, password: &str) -> Result<Option<UserDetail>>;
-    async fn update_user(&self, id: Uuid, detail: UserDetailToAddOrUpdate) -> Result<bool>;
+    /// A page of the users matching `filter`. In cursor mode `sort` must be by ID.
+    async fn get_user_list(&self, pagination: Pagination, sort: UserSort, filter: UserFilter) -> Result<Page<UserDetail>>;
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<UserDetail>>;
+    /// Live users matching every word of `query` in alias, username or email, best matches first.
+    async fn search_users(&self, query: &str, limit: i64) -> Result<Vec<UserSearchHit<UserDetail>>>;
+    /// Replaces the user's fields but the password. With `version` only if the user is still at that version, failing with
+    /// [`Error::VersionMismatch`](crate::Error::VersionMismatch) otherwise.
+    async fn update_user(&self, id: Uuid, detail: UserDetailToUpdate, version: Option<i64>) -> Result<bool>;
+    /// Writes only the fields `patch` sets. Like every other write of the user's fields it bumps `updated_at` and `version`.
+    async fn patch_user(&self, id: Uuid, patch: UserPatch) -> Result<bool>;
+    async fn update_user_password(&self, id: Uuid, password: &str) -> Result<bool>;
+    /// Replaces the password hash with `new` if it is still `old`, for rehashing the same password. Unlike a change of the
+    /// password it leaves `updated_at` and `version` alone.
+    async fn rehash_user_password(&self, id: Uuid, old: &str, new: &str) -> Result<bool>;
+    async fn update_user_email(&self, id: Uuid, email: &str) -> Result<bool>;
+    async fn set_user_tokens_valid_after(&self, id: Uuid, at: DateTime<Utc>) -> Result<bool>;
+    /// Undoes `remove_user`. Returns `false` if the user doesn't exist or isn't deleted.
+    async fn restore_user(&self, id: Uuid) -> Result<bool>;
+    /// Permanently deletes a deleted user with their refresh tokens and role assignments. Returns `false` if the user doesn't exist or isn't deleted.
+    async fn purge_user(&self, id: Uuid) -> Result<bool>;
+    /// Permanently deletes every user deleted before `before`, like `purge_user`. Returns how many were purged.
+    async fn purge_users_deleted_before(&self, before: DateTime<Utc>) -> Result<u64>;
+    async fn set_user_type(&self, id: Uuid, user_type: UserType) -> Result<bool>;
+    /// Whether [`mark_bootstrapped`](Self::mark_bootstrapped) was ever recorded.
+    async fn is_bootstrapped(&self) -> Result<bool>;
+    /// Records for good that the first admin was bootstrapped. Returns `false` if that was recorded before. Of two
+    /// transactions recording it at once, the second waits for the first and gets `false` once it commits.
+    async fn mark_bootstrapped(&self) -> Result<bool>;
+}
+
+#[async_trait]
+pub trait TokenDb: Send + Sync {
+    async fn add_refresh_token(&self, user_id: Uuid, family_id: Uuid, token_hash: &str, expires_at: DateTime<Utc>) -> Result<Uuid>;
+    async fn get_refresh_token(&self, token_hash: &str) -> Result<Option<RefreshToken>>;
+    /// Marks an unused, unrevoked token as used. Returns `false` if another caller consumed it first.
+    async fn use_refresh_token(&self, id: Uuid) -> Result<bool>;
+    async fn revoke_refresh_token_family(&self, family_id: Uuid) -> Result<u64>;
+    async fn revoke_user_refresh_tokens(&self, user_id: Uuid) -> Result<u64>;
+    /// Records a revoked access token until `expires_at`, after which its signature check rejects it anyway.
+    async fn revoke_access_token(&self, jti: Uuid, expires_at: DateTime<Utc>) -> Result<()>;
+    async fn is_access_token_revoked(&self, jti: Uuid) -> Result<bool>;
+}
+
+#[async_trait]
+pub trait PermissionDb: Send + Sync {
+    async fn add_role(&self, role: RoleToAddOrUpdate) -> Result<Uuid>;
+    /// Replaces name, description and the whole permission set of a role.
+    async fn update_role(&self, id: Uuid, role: RoleToAddOrUpdate) -> Result<bool>;
+    /// Deletes a role together with its permissions and assignments.
+    async fn remove_role(&self, id: Uuid) -> Result<bool>;
+    async fn get_role(&self, id: Uuid) -> Result<Option<Role>>;
+    async fn get_role_list(&self) -> Result<Vec<Role>>;
+    async fn assign_role(&self, user_id: Uuid, role_id: Uuid) -> Result<bool>;
+    async fn unassign_role(&self, user_id: Uuid, role_id: Uuid) -> Result<bool>;
+    async fn get_user_roles(&self, user_id: Uuid) -> Result<Vec<Role>>;
+    async fn get_role_user_ids(&self, role_id: Uuid) -> Result<Vec<Uuid>>;
+    /// Union of the permissions of every role assigned to the user.
+    async fn get_user_permissions(&self, user_id: Uuid) -> Result<Vec<Permission>>;
+}
+
+/// Schema migrations. Connecting to a backend never migrates by itself.
+#[async_trait]
+pub trait MigrateDb: Send + Sync {
+    /// Every known migration, oldest first.
+    async fn migration_status(&self) -> Result<Vec<MigrationStatus>>;
+    /// Applies pending migrations up to and including version `to`, or all of them. With `dry_run` nothing is changed.
+    async fn migrate_up(&self, to: Option<i64>, dry_run: bool) -> Result<Vec<MigrationStep>>;
+    /// Reverts applied migrations newer than version `to`, or only the latest one. With `dry_run` nothing is changed.
+    async fn migrate_down(&self, to: Option<i64>, dry_run: bool) -> Result<Vec<MigrationStep>>;
+}
+
+/// Permissions that no longer exist in code are skipped.
+pub(crate) fn parse_permissions(permissions: Vec<String>) -> Vec<Permission> {
+    let mut permissions: Vec<Permission> = permissions.iter().filter_map(|permission| permission.parse().ok()).collect();
+    permissions.sort();
+    permissions
 }
@@ -1,3 +1,7 @@
+pub mod migrate_storage;
+pub mod permission_storage;
+pub mod token_storage;
+pub mod transaction_storage;
 pub mod user_storage;
 
 use std::sync::Arc;
@@ -0,0 +1,24 @@
+use crate::{
+    Result,
+    db::{MigrateDb, any_impl::AnyDbImpl},
+    migrate::{MigrationStatus, MigrationStep},
+};
+use async_trait::async_trait;
+
+#[async_trait]
+impl MigrateDb for AnyDbImpl {
+    /// Lists the migrations of the wrapped backend.
+    async fn migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        self.inner.migration_status().await
+    }
+
+    /// Applies pending migrations of the wrapped backend.
+    async fn migrate_up(&self, to: Option<i64>, dry_run: bool) -> Result<Vec<MigrationStep>> {
+        self.inner.migrate_up(to, dry_run).await
+    }
+
+    /// Reverts applied migrations of the wrapped backend.
+    async fn migrate_down(&self, to: Option<i64>, dry_run: bool) -> Result<Vec<MigrationStep>> {
+        self.inner.migrate_down(to, dry_run).await
+    }
+}
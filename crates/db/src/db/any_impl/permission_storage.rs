@@ -0,0 +1,60 @@
+use crate::{
+    Result,
+    db::{PermissionDb, any_impl::AnyDbImpl},
+};
+use async_trait::async_trait;
+use shared::models::permission::{Permission, Role, RoleToAddOrUpdate};
+use uuid::Uuid;
+
+#[async_trait]
+impl PermissionDb for AnyDbImpl {
+    /// Inserts a new role and its permissions into the 'roles' and 'role_permissions' tables and returns the new ID.
+    async fn add_role(&self, role: RoleToAddOrUpdate) -> Result<Uuid> {
+        self.inner.add_role(role).await
+    }
+
+    /// Updates an existing role by ID and replaces its permissions.
+    async fn update_role(&self, id: Uuid, role: RoleToAddOrUpdate) -> Result<bool> {
+        self.inner.update_role(id, role).await
+    }
+
+    /// Deletes a role by ID together with its permissions and assignments.
+    async fn remove_role(&self, id: Uuid) -> Result<bool> {
+        self.inner.remove_role(id).await
+    }
+
+    /// Fetches a single role by ID from the 'roles' table.
+    async fn get_role(&self, id: Uuid) -> Result<Option<Role>> {
+        self.inner.get_role(id).await
+    }
+
+    /// Fetches every role from the 'roles' table.
+    async fn get_role_list(&self) -> Result<Vec<Role>> {
+        self.inner.get_role_list().await
+    }
+
+    /// Assigns a role to a user. Returns `false` if the user already had it.
+    async fn assign_role(&self, user_id: Uuid, role_id: Uuid) -> Result<bool> {
+        self.inner.assign_role(user_id, role_id).await
+    }
+
+    /// Removes a role from a user.
+    async fn unassign_role(&self, user_id: Uuid, role_id: Uuid) -> Result<bool> {
+        self.inner.unassign_role(user_id, role_id).await
+    }
+
+    /// Fetches every role assigned to a user.
+    async fn get_user_roles(&self, user_id: Uuid) -> Result<Vec<Role>> {
+        self.inner.get_user_roles(user_id).await
+    }
+
+    /// Fetches the IDs of every user a role is assigned to.
+    async fn get_role_user_ids(&self, role_id: Uuid) -> Result<Vec<Uuid>> {
+        self.inner.get_role_user_ids(role_id).await
+    }
+
+    /// Union of the permissions of every role assigned to the user.
+    async fn get_user_permissions(&self, user_id: Uuid) -> Result<Vec<Permission>> {
+        self.inner.get_user_permissions(user_id).await
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{
+    Result,
+    db::{TokenDb, any_impl::AnyDbImpl},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use shared::models::token::RefreshToken;
+use uuid::Uuid;
+
+#[async_trait]
+impl TokenDb for AnyDbImpl {
+    /// Inserts a new refresh token record into the 'refresh_tokens' table and returns the new ID.
+    async fn add_refresh_token(&self, user_id: Uuid, family_id: Uuid, token_hash: &str, expires_at: DateTime<Utc>) -> Result<Uuid> {
+        self.inner.add_refresh_token(user_id, family_id, token_hash, expires_at).await
+    }
+
+    /// Fetches a refresh token record by the hash of the token.
+    async fn get_refresh_token(&self, token_hash: &str) -> Result<Option<RefreshToken>> {
+        self.inner.get_refresh_token(token_hash).await
+    }
+
+    /// Marks an unused, unrevoked refresh token as used.
+    async fn use_refresh_token(&self, id: Uuid) -> Result<bool> {
+        self.inner.use_refresh_token(id).await
+    }
+
+    /// Revokes every refresh token descending from the same login.
+    async fn revoke_refresh_token_family(&self, family_id: Uuid) -> Result<u64> {
+        self.inner.revoke_refresh_token_family(family_id).await
+    }
+
+    /// Revokes every refresh token of a user.
+    async fn revoke_user_refresh_tokens(&self, user_id: Uuid) -> Result<u64> {
+        self.inner.revoke_user_refresh_tokens(user_id).await
+    }
+
+    /// Inserts a revoked access token into the 'revoked_tokens' table, dropping entries that have expired meanwhile.
+    async fn revoke_access_token(&self, jti: Uuid, expires_at: DateTime<Utc>) -> Result<()> {
+        self.inner.revoke_access_token(jti, expires_at).await
+    }
+
+    async fn is_access_token_revoked(&self, jti: Uuid) -> Result<bool> {
+        self.inner.is_access_token_revoked(jti).await
+    }
+}
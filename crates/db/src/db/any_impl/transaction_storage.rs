@@ -0,0 +1,13 @@
+use crate::{
+    Result,
+    db::{DbTransaction, FullDb, any_impl::AnyDbImpl},
+};
+use async_trait::async_trait;
+
+#[async_trait]
+impl FullDb for AnyDbImpl {
+    /// Starts a transaction of the wrapped storage.
+    async fn begin(&self) -> Result<Box<dyn DbTransaction>> {
+        self.inner.begin().await
+    }
+}
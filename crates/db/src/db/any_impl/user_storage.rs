@@ -3,9 +3,10 @@ use crate::{
     db::{UserDb, any_impl::AnyDbImpl},
 };
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use shared::models::{
-    Pagination,
-    user::{UserDetail, UserDetailToAddOrUpdate, UserType},
+    Page, Pagination,
+    user::{UserDetail, UserDetailToAddOrUpdate, UserDetailToUpdate, UserFilter, UserPatch, UserSearchHit, UserSort, UserType},
 };
 use uuid::Uuid;
 
@@ -30,17 +31,77 @@ impl UserDb for AnyDbImpl {
         self.inner.get_user(id).await
     }
 
-    async fn get_user_by_validate(&self, username: &str, password: &str) -> Result<Option<UserDetail>> {
-        self.inner.get_user_by_validate(username, password).await
+    /// Fetches a single live user record by username from the 'users' table.
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<UserDetail>> {
+        self.inner.get_user_by_username(username).await
     }
 
-    /// Fetches a paginated list of user records from the 'users' table.
-    async fn get_user_list(&self, pagination: Pagination) -> Result<Vec<UserDetail>> {
-        self.inner.get_user_list(pagination).await
+    /// Searches live user records by alias, username and email.
+    async fn search_users(&self, query: &str, limit: i64) -> Result<Vec<UserSearchHit<UserDetail>>> {
+        self.inner.search_users(query, limit).await
     }
 
-    /// Updates an existing user record in the 'users' table by ID.
-    async fn update_user(&self, id: Uuid, detail: UserDetailToAddOrUpdate) -> Result<bool> {
-        self.inner.update_user(id, detail).await
+    /// Fetches a page of user records from the 'users' table matching `filter`.
+    async fn get_user_list(&self, pagination: Pagination, sort: UserSort, filter: UserFilter) -> Result<Page<UserDetail>> {
+        self.inner.get_user_list(pagination, sort, filter).await
+    }
+
+    /// Updates an existing user record in the 'users' table by ID, if it is at `version` when given.
+    async fn update_user(&self, id: Uuid, detail: UserDetailToUpdate, version: Option<i64>) -> Result<bool> {
+        self.inner.update_user(id, detail, version).await
+    }
+
+    /// Updates the fields `patch` sets of a live user, leaving the others untouched.
+    async fn patch_user(&self, id: Uuid, patch: UserPatch) -> Result<bool> {
+        self.inner.patch_user(id, patch).await
+    }
+
+    /// Replaces the stored password hash of an existing user.
+    async fn update_user_password(&self, id: Uuid, password: &str) -> Result<bool> {
+        self.inner.update_user_password(id, password).await
+    }
+
+    /// Swaps the stored password hash of a live user for an equivalent one.
+    async fn rehash_user_password(&self, id: Uuid, old: &str, new: &str) -> Result<bool> {
+        self.inner.rehash_user_password(id, old, new).await
+    }
+
+    /// Replaces the email address of a live user.
+    async fn update_user_email(&self, id: Uuid, email: &str) -> Result<bool> {
+        self.inner.update_user_email(id, email).await
+    }
+
+    /// Rejects every access token of the user issued at or before `at`.
+    async fn set_user_tokens_valid_after(&self, id: Uuid, at: DateTime<Utc>) -> Result<bool> {
+        self.inner.set_user_tokens_valid_after(id, at).await
+    }
+
+    /// Undoes `remove_user`, making a deleted user live again.
+    async fn restore_user(&self, id: Uuid) -> Result<bool> {
+        self.inner.restore_user(id).await
+    }
+
+    /// Permanently deletes a deleted user record together with its refresh tokens and role assignments.
+    async fn purge_user(&self, id: Uuid) -> Result<bool> {
+        self.inner.purge_user(id).await
+    }
+
+    /// Permanently deletes every user record deleted before `before`, see `purge_user`.
+    async fn purge_users_deleted_before(&self, before: DateTime<Utc>) -> Result<u64> {
+        self.inner.purge_users_deleted_before(before).await
+    }
+
+    /// Changes the type of a live user.
+    async fn set_user_type(&self, id: Uuid, user_type: UserType) -> Result<bool> {
+        self.inner.set_user_type(id, user_type).await
+    }
+
+    async fn is_bootstrapped(&self) -> Result<bool> {
+        self.inner.is_bootstrapped().await
+    }
+
+    /// Records that the first admin was bootstrapped.
+    async fn mark_bootstrapped(&self) -> Result<bool> {
+        self.inner.mark_bootstrapped().await
     }
 }
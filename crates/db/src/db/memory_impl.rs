@@ -0,0 +1,85 @@
+pub mod migrate_storage;
+pub mod permission_storage;
+pub mod token_storage;
+pub mod transaction_storage;
+pub mod user_storage;
+
+use std::sync::{
+    Arc, Mutex, MutexGuard, PoisonError,
+    atomic::{AtomicBool, Ordering},
+};
+
+use chrono::{DateTime, Utc};
+use dashmap::{DashMap, DashSet};
+use shared::models::{permission::Role, token::RefreshToken, user::UserDetail};
+use uuid::Uuid;
+
+/// Storage kept entirely in process memory, selected with a `memory:` URL.
+///
+/// Meant for tests and demos, everything is lost when the process exits.
+#[derive(Default)]
+pub struct InMemoryDbImpl {
+    users: Arc<DashMap<Uuid, UserRecord>>,
+    refresh_tokens: Arc<DashMap<Uuid, RefreshToken>>,
+    /// Revoked access token IDs and when they expire.
+    revoked_tokens: Arc<DashMap<Uuid, DateTime<Utc>>>,
+    roles: Arc<DashMap<Uuid, Role>>,
+    /// Role assignments as `(user_id, role_id)` pairs.
+    user_roles: Arc<DashSet<(Uuid, Uuid)>>,
+    /// Whether the first admin was bootstrapped.
+    bootstrapped: Arc<AtomicBool>,
+    /// Held by every write that must keep usernames and emails unique, from its check to its write.
+    user_writes: Arc<Mutex<()>>,
+    /// Set on the handles `begin` returns, which work on a copy of the data.
+    transaction: Option<Box<MemoryTransaction>>,
+}
+
+#[derive(Clone, PartialEq)]
+struct UserRecord {
+    detail: UserDetail,
+}
+
+/// The data a transaction started from and the storage its changes are written back to on commit.
+struct MemoryTransaction {
+    base: InMemoryDbImpl,
+    target: InMemoryDbImpl,
+}
+
+impl InMemoryDbImpl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A copy of the data that shares nothing with `self`.
+    fn snapshot(&self) -> Self {
+        Self {
+            users: Arc::new((*self.users).clone()),
+            refresh_tokens: Arc::new((*self.refresh_tokens).clone()),
+            revoked_tokens: Arc::new((*self.revoked_tokens).clone()),
+            roles: Arc::new((*self.roles).clone()),
+            user_roles: Arc::new((*self.user_roles).clone()),
+            bootstrapped: Arc::new(AtomicBool::new(self.bootstrapped.load(Ordering::SeqCst))),
+            user_writes: Arc::default(),
+            transaction: None,
+        }
+    }
+
+    /// Held across the unique checks of a user write and the write itself, so concurrent writes can't both pass them.
+    fn lock_user_writes(&self) -> MutexGuard<'_, ()> {
+        self.user_writes.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Another handle on the same data.
+    fn share(&self) -> Self {
+        Self {
+            users: self.users.clone(),
+            refresh_tokens: self.refresh_tokens.clone(),
+            revoked_tokens: self.revoked_tokens.clone(),
+            roles: self.roles.clone(),
+            user_roles: self.user_roles.clone(),
+            bootstrapped: self.bootstrapped.clone(),
+            user_writes: self.user_writes.clone(),
+            transaction: None,
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use crate::{
+    Result,
+    db::{MigrateDb, memory_impl::InMemoryDbImpl},
+    migrate::{MigrationStatus, MigrationStep},
+};
+use async_trait::async_trait;
+
+#[async_trait]
+impl MigrateDb for InMemoryDbImpl {
+    /// In-memory storage has no schema, so there are no migrations.
+    async fn migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        Ok(Vec::new())
+    }
+
+    /// Nothing to apply.
+    async fn migrate_up(&self, _to: Option<i64>, _dry_run: bool) -> Result<Vec<MigrationStep>> {
+        Ok(Vec::new())
+    }
+
+    /// Nothing to revert.
+    async fn migrate_down(&self, _to: Option<i64>, _dry_run: bool) -> Result<Vec<MigrationStep>> {
+        Ok(Vec::new())
+    }
+}
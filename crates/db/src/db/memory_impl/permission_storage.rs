@@ -0,0 +1,106 @@
+use crate::{
+    Error, Result,
+    db::{PermissionDb, memory_impl::InMemoryDbImpl},
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use shared::models::permission::{Permission, Role, RoleToAddOrUpdate};
+use uuid::Uuid;
+
+#[async_trait]
+impl PermissionDb for InMemoryDbImpl {
+    /// Inserts a new role with its permissions and returns the new ID.
+    async fn add_role(&self, role: RoleToAddOrUpdate) -> Result<Uuid> {
+        self.check_role_conflicts(&role.name, None)?;
+        let id = Uuid::now_v7();
+        let now = Utc::now();
+        let role = Role {
+            id,
+            name: role.name,
+            description: role.description,
+            permissions: normalize(role.permissions),
+            created_at: now,
+            updated_at: now,
+        };
+        self.roles.insert(id, role);
+        Ok(id)
+    }
+
+    /// Updates an existing role by ID and replaces its permissions.
+    async fn update_role(&self, id: Uuid, role: RoleToAddOrUpdate) -> Result<bool> {
+        self.check_role_conflicts(&role.name, Some(id))?;
+        match self.roles.get_mut(&id) {
+            Some(mut stored) => {
+                stored.name = role.name;
+                stored.description = role.description;
+                stored.permissions = normalize(role.permissions);
+                stored.updated_at = Utc::now();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Deletes a role by ID together with its assignments.
+    async fn remove_role(&self, id: Uuid) -> Result<bool> {
+        self.user_roles.retain(|(_, role_id)| *role_id != id);
+        Ok(self.roles.remove(&id).is_some())
+    }
+
+    /// Fetches a single role by ID.
+    async fn get_role(&self, id: Uuid) -> Result<Option<Role>> {
+        Ok(self.roles.get(&id).map(|role| role.clone()))
+    }
+
+    /// Fetches every role ordered by name.
+    async fn get_role_list(&self) -> Result<Vec<Role>> {
+        let mut list: Vec<Role> = self.roles.iter().map(|role| role.clone()).collect();
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(list)
+    }
+
+    /// Assigns a role to a user. Returns `false` if the user already had it.
+    async fn assign_role(&self, user_id: Uuid, role_id: Uuid) -> Result<bool> {
+        Ok(self.user_roles.insert((user_id, role_id)))
+    }
+
+    /// Removes a role from a user.
+    async fn unassign_role(&self, user_id: Uuid, role_id: Uuid) -> Result<bool> {
+        Ok(self.user_roles.remove(&(user_id, role_id)).is_some())
+    }
+
+    /// Fetches every role assigned to a user ordered by name.
+    async fn get_user_roles(&self, user_id: Uuid) -> Result<Vec<Role>> {
+        let role_ids: Vec<Uuid> = self.user_roles.iter().filter(|pair| pair.0 == user_id).map(|pair| pair.1).collect();
+        let mut list: Vec<Role> = role_ids.iter().filter_map(|id| self.roles.get(id).map(|role| role.clone())).collect();
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(list)
+    }
+
+    /// Fetches the IDs of every user a role is assigned to.
+    async fn get_role_user_ids(&self, role_id: Uuid) -> Result<Vec<Uuid>> {
+        Ok(self.user_roles.iter().filter(|pair| pair.1 == role_id).map(|pair| pair.0).collect())
+    }
+
+    /// Union of the permissions of every role assigned to the user.
+    async fn get_user_permissions(&self, user_id: Uuid) -> Result<Vec<Permission>> {
+        let roles = self.get_user_roles(user_id).await?;
+        Ok(normalize(roles.into_iter().flat_map(|role| role.permissions).collect()))
+    }
+}
+
+fn normalize(mut permissions: Vec<Permission>) -> Vec<Permission> {
+    permissions.sort();
+    permissions.dedup();
+    permissions
+}
+
+impl InMemoryDbImpl {
+    /// Mirrors the unique index on role names of the SQL backends. `except` is the role being written.
+    fn check_role_conflicts(&self, name: &str, except: Option<Uuid>) -> Result<()> {
+        if self.roles.iter().any(|role| role.name == name && Some(role.id) != except) {
+            return Err(Error::Conflict { field: "name" });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{
+    Result,
+    db::{TokenDb, memory_impl::InMemoryDbImpl},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use shared::models::token::RefreshToken;
+use uuid::Uuid;
+
+#[async_trait]
+impl TokenDb for InMemoryDbImpl {
+    /// Inserts a new refresh token record and returns the new ID.
+    async fn add_refresh_token(&self, user_id: Uuid, family_id: Uuid, token_hash: &str, expires_at: DateTime<Utc>) -> Result<Uuid> {
+        let id = Uuid::now_v7();
+        let token = RefreshToken {
+            id,
+            user_id,
+            family_id,
+            token_hash: token_hash.to_owned(),
+            expires_at,
+            used_at: None,
+            revoked: false,
+            created_at: Utc::now(),
+        };
+        self.refresh_tokens.insert(id, token);
+        Ok(id)
+    }
+
+    /// Fetches a refresh token record by the hash of the token.
+    async fn get_refresh_token(&self, token_hash: &str) -> Result<Option<RefreshToken>> {
+        Ok(self.refresh_tokens.iter().find(|token| token.token_hash == token_hash).map(|token| token.clone()))
+    }
+
+    /// Marks an unused, unrevoked refresh token as used.
+    async fn use_refresh_token(&self, id: Uuid) -> Result<bool> {
+        match self.refresh_tokens.get_mut(&id) {
+            Some(mut token) if !token.is_used() && !token.revoked => {
+                token.used_at = Some(Utc::now());
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Revokes every refresh token descending from the same login.
+    async fn revoke_refresh_token_family(&self, family_id: Uuid) -> Result<u64> {
+        let mut revoked = 0;
+        for mut token in self.refresh_tokens.iter_mut() {
+            if token.family_id == family_id && !token.revoked {
+                token.revoked = true;
+                revoked += 1;
+            }
+        }
+        Ok(revoked)
+    }
+
+    /// Revokes every refresh token of a user.
+    async fn revoke_user_refresh_tokens(&self, user_id: Uuid) -> Result<u64> {
+        let mut revoked = 0;
+        for mut token in self.refresh_tokens.iter_mut() {
+            if token.user_id == user_id && !token.revoked {
+                token.revoked = true;
+                revoked += 1;
+            }
+        }
+        Ok(revoked)
+    }
+
+    /// Records a revoked access token, dropping entries that have expired meanwhile.
+    async fn revoke_access_token(&self, jti: Uuid, expires_at: DateTime<Utc>) -> Result<()> {
+        let now = Utc::now();
+        self.revoked_tokens.retain(|_, expires_at| *expires_at >= now);
+        self.revoked_tokens.insert(jti, expires_at);
+        Ok(())
+    }
+
+    async fn is_access_token_revoked(&self, jti: Uuid) -> Result<bool> {
+        Ok(self.revoked_tokens.contains_key(&jti))
+    }
+}
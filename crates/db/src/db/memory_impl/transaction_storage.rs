@@ -0,0 +1,108 @@
+use std::{collections::HashMap, hash::Hash, sync::atomic::Ordering};
+
+use crate::{
+    Error, Result,
+    db::{
+        DbTransaction, FullDb,
+        memory_impl::{InMemoryDbImpl, MemoryTransaction, UserRecord, user_storage::conflict},
+    },
+};
+use async_trait::async_trait;
+use dashmap::{DashMap, DashSet};
+use uuid::Uuid;
+
+#[async_trait]
+impl FullDb for InMemoryDbImpl {
+    /// Starts a transaction on a copy of the data. Committing writes back only the records it changed, the last
+    /// write of a record wins. The unique checks of users are repeated against the data as it is at commit.
+    async fn begin(&self) -> Result<Box<dyn DbTransaction>> {
+        let mut handle = self.snapshot();
+        handle.transaction = Some(Box::new(MemoryTransaction {
+            base: self.snapshot(),
+            target: self.share(),
+        }));
+        Ok(Box::new(handle))
+    }
+}
+
+#[async_trait]
+impl DbTransaction for InMemoryDbImpl {
+    async fn commit(self: Box<Self>) -> Result<()> {
+        let Some(transaction) = &self.transaction else {
+            return Ok(());
+        };
+        let (base, target) = (&transaction.base, &transaction.target);
+        let _writes = target.lock_user_writes();
+        // Stands in for the primary key of the SQL backends' `bootstrap` table.
+        let bootstraps = self.bootstrapped.load(Ordering::SeqCst) && !base.bootstrapped.load(Ordering::SeqCst);
+        if bootstraps && target.bootstrapped.load(Ordering::SeqCst) {
+            return Err(Error::Conflict { field: "bootstrap" });
+        }
+        check_user_conflicts(&target.users, &base.users, &self.users)?;
+        merge(&target.users, &base.users, &self.users);
+        merge(&target.refresh_tokens, &base.refresh_tokens, &self.refresh_tokens);
+        merge(&target.revoked_tokens, &base.revoked_tokens, &self.revoked_tokens);
+        merge(&target.roles, &base.roles, &self.roles);
+        merge_set(&target.user_roles, &base.user_roles, &self.user_roles);
+        if bootstraps {
+            target.bootstrapped.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    /// Discards the copy, nothing was written to the storage yet.
+    async fn rollback(self: Box<Self>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Fails if a user written between `base` and `changed` would share a unique field with another live user once merged into `target`.
+fn check_user_conflicts(target: &DashMap<Uuid, UserRecord>, base: &DashMap<Uuid, UserRecord>, changed: &DashMap<Uuid, UserRecord>) -> Result<()> {
+    let written: Vec<UserRecord> = changed.iter().filter(|entry| base.get(entry.key()).is_none_or(|before| *before != *entry.value())).map(|entry| entry.value().clone()).collect();
+    // Records of `target` the transaction neither wrote nor removed stay as they are.
+    let untouched = |id: &Uuid| match (base.get(id), changed.get(id)) {
+        (Some(before), Some(now)) => *before == *now,
+        (Some(_), None) => false,
+        (None, _) => true,
+    };
+    let mut merged: HashMap<Uuid, UserRecord> = target.iter().filter(|entry| untouched(entry.key())).map(|entry| (*entry.key(), entry.value().clone())).collect();
+    merged.extend(written.iter().map(|record| (record.detail.id, record.clone())));
+    for record in written.iter().filter(|record| !record.detail.is_deleted) {
+        let detail = &record.detail;
+        if let Some(field) = merged.values().filter(|other| other.detail.id != detail.id).find_map(|other| conflict(&detail.username, &detail.email, &other.detail)) {
+            return Err(Error::Conflict { field });
+        }
+    }
+    Ok(())
+}
+
+/// Applies what changed from `base` to `changed` to `target`.
+fn merge<K, V>(target: &DashMap<K, V>, base: &DashMap<K, V>, changed: &DashMap<K, V>)
+where
+    K: Eq + Hash + Clone,
+    V: PartialEq + Clone,
+{
+    for entry in changed.iter() {
+        if base.get(entry.key()).is_none_or(|before| *before != *entry.value()) {
+            target.insert(entry.key().clone(), entry.value().clone());
+        }
+    }
+    for entry in base.iter() {
+        if !changed.contains_key(entry.key()) {
+            target.remove(entry.key());
+        }
+    }
+}
+
+fn merge_set<K: Eq + Hash + Clone>(target: &DashSet<K>, base: &DashSet<K>, changed: &DashSet<K>) {
+    for key in changed.iter() {
+        if !base.contains(&*key) {
+            target.insert(key.clone());
+        }
+    }
+    for key in base.iter() {
+        if !changed.contains(&*key) {
+            target.remove(&*key);
+        }
+    }
+}
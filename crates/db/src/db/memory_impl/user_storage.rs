@@ -0,0 +1,316 @@
+use std::sync::atomic::Ordering;
+
+use crate::{
+    Error, Result, search,
+    db::{
+        UserDb,
+        memory_impl::{InMemoryDbImpl, UserRecord},
+    },
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use shared::models::{
+    Page, Pagination, SortOrder,
+    user::{DeletedUsers, UserDetail, UserDetailToAddOrUpdate, UserDetailToUpdate, UserFilter, UserPatch, UserSearchHit, UserSort, UserSortKey, UserType},
+};
+use uuid::Uuid;
+
+#[async_trait]
+impl UserDb for InMemoryDbImpl {
+    async fn exists_user_type(&self, user_type: UserType) -> Result<bool> {
+        Ok(self.users.iter().any(|record| !record.detail.is_deleted && record.detail.user_type == user_type))
+    }
+
+    /// Inserts a new user record and returns the new ID.
+    async fn add_user(&self, user_type: UserType, detail: UserDetailToAddOrUpdate) -> Result<Uuid> {
+        let _writes = self.lock_user_writes();
+        self.check_user_conflicts(&detail.username, &detail.email, None)?;
+        let id = Uuid::now_v7();
+        let now = Utc::now();
+        let detail = UserDetail {
+            id,
+            user_type,
+            alias: detail.alias,
+            username: detail.username,
+            password: detail.password,
+            email: detail.email,
+            created_at: now,
+            updated_at: now,
+            tokens_valid_after: None,
+            is_deleted: false,
+            deleted_at: None,
+            version: 1,
+        };
+        self.users.insert(id, UserRecord { detail });
+        Ok(id)
+    }
+
+    /// Soft deletes a user record by ID, recording when.
+    async fn remove_user(&self, id: Uuid) -> Result<bool> {
+        match self.users.get_mut(&id) {
+            Some(mut record) if !record.detail.is_deleted => {
+                record.detail.is_deleted = true;
+                record.detail.deleted_at = Some(Utc::now());
+                touch(&mut record.detail);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Fetches a single live user record by ID.
+    async fn get_user(&self, id: Uuid) -> Result<Option<UserDetail>> {
+        Ok(self.users.get(&id).filter(|record| !record.detail.is_deleted).map(|record| record.detail.clone()))
+    }
+
+    /// Fetches a single live user record by username.
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<UserDetail>> {
+        Ok(self
+            .users
+            .iter()
+            .find(|record| !record.detail.is_deleted && record.detail.username == username)
+            .map(|record| record.detail.clone()))
+    }
+
+    /// Searches live user records by alias, username and email.
+    async fn search_users(&self, query: &str, limit: i64) -> Result<Vec<UserSearchHit<UserDetail>>> {
+        let live = self.users.iter().filter(|record| !record.detail.is_deleted).map(|record| record.detail.clone()).collect();
+        Ok(search::rank(live, &search::terms(query), limit.max(0) as usize))
+    }
+
+    /// Fetches a page of user records matching `filter`.
+    async fn get_user_list(&self, pagination: Pagination, sort: UserSort, filter: UserFilter) -> Result<Page<UserDetail>> {
+        let mut list: Vec<UserDetail> = self.users.iter().filter(|record| matches(&record.detail, &filter)).map(|record| record.detail.clone()).collect();
+        let total = list.len() as i64;
+        list.sort_by(|a, b| {
+            let ordering = match sort.sort {
+                UserSortKey::Id => a.id.cmp(&b.id),
+                UserSortKey::CreatedAt => a.created_at.cmp(&b.created_at),
+                UserSortKey::UpdatedAt => a.updated_at.cmp(&b.updated_at),
+                UserSortKey::Username => a.username.cmp(&b.username),
+                UserSortKey::Alias => a.alias.cmp(&b.alias),
+            }
+            .then_with(|| a.id.cmp(&b.id));
+            if sort.order == SortOrder::Desc { ordering.reverse() } else { ordering }
+        });
+        if let Some(after) = pagination.cursor() {
+            list.retain(|detail| if sort.order == SortOrder::Desc { detail.id < after } else { detail.id > after });
+        }
+
+        // One more row than requested tells whether there is a next page.
+        let (offset, limit) = pagination.offset_limit();
+        let list = list.into_iter().skip(offset as usize).take(limit.map_or(usize::MAX, |limit| limit.saturating_add(1) as usize)).collect();
+        Ok(Page::new(list, total, &pagination, |user| (sort.sort == UserSortKey::Id).then_some(user.id)))
+    }
+
+    /// Updates an existing live user record by ID, if it is at `version` when given.
+    async fn update_user(&self, id: Uuid, detail: UserDetailToUpdate, version: Option<i64>) -> Result<bool> {
+        let _writes = self.lock_user_writes();
+        self.check_user_conflicts(&detail.username, &detail.email, Some(id))?;
+        match self.users.get_mut(&id) {
+            Some(mut record) if !record.detail.is_deleted => {
+                if let Some(expected) = version
+                    && record.detail.version != expected
+                {
+                    return Err(Error::VersionMismatch { expected });
+                }
+                record.detail.alias = detail.alias;
+                record.detail.username = detail.username;
+                record.detail.email = detail.email;
+                touch(&mut record.detail);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Updates the fields `patch` sets of a live user, leaving the others untouched.
+    async fn patch_user(&self, id: Uuid, patch: UserPatch) -> Result<bool> {
+        let _writes = self.lock_user_writes();
+        if let Some(username) = &patch.username
+            && let Some(email) = self.users.get(&id).map(|record| record.detail.email.clone())
+        {
+            self.check_user_conflicts(username, &email, Some(id))?;
+        }
+        match self.users.get_mut(&id) {
+            Some(mut record) if !record.detail.is_deleted => {
+                if let Some(alias) = patch.alias {
+                    record.detail.alias = alias;
+                }
+                if let Some(username) = patch.username {
+                    record.detail.username = username;
+                }
+                touch(&mut record.detail);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Replaces the stored password hash of an existing user.
+    async fn update_user_password(&self, id: Uuid, password: &str) -> Result<bool> {
+        match self.users.get_mut(&id) {
+            Some(mut record) if !record.detail.is_deleted => {
+                record.detail.password = password.to_owned();
+                touch(&mut record.detail);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Swaps the stored password hash of a live user for an equivalent one.
+    async fn rehash_user_password(&self, id: Uuid, old: &str, new: &str) -> Result<bool> {
+        match self.users.get_mut(&id) {
+            Some(mut record) if !record.detail.is_deleted && record.detail.password == old => {
+                record.detail.password = new.to_owned();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Replaces the email address of a live user.
+    async fn update_user_email(&self, id: Uuid, email: &str) -> Result<bool> {
+        let _writes = self.lock_user_writes();
+        if let Some(username) = self.users.get(&id).map(|record| record.detail.username.clone()) {
+            self.check_user_conflicts(&username, email, Some(id))?;
+        }
+        match self.users.get_mut(&id) {
+            Some(mut record) if !record.detail.is_deleted => {
+                record.detail.email = email.to_owned();
+                touch(&mut record.detail);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Rejects every access token of the user issued at or before `at`.
+    async fn set_user_tokens_valid_after(&self, id: Uuid, at: DateTime<Utc>) -> Result<bool> {
+        match self.users.get_mut(&id) {
+            Some(mut record) if !record.detail.is_deleted => {
+                record.detail.tokens_valid_after = Some(at);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Undoes `remove_user`, making a deleted user live again.
+    async fn restore_user(&self, id: Uuid) -> Result<bool> {
+        let _writes = self.lock_user_writes();
+        if let Some((username, email)) = self.users.get(&id).map(|record| (record.detail.username.clone(), record.detail.email.clone())) {
+            self.check_user_conflicts(&username, &email, Some(id))?;
+        }
+        match self.users.get_mut(&id) {
+            Some(mut record) if record.detail.is_deleted => {
+                record.detail.is_deleted = false;
+                record.detail.deleted_at = None;
+                touch(&mut record.detail);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Permanently deletes a deleted user record together with its refresh tokens and role assignments.
+    async fn purge_user(&self, id: Uuid) -> Result<bool> {
+        if self.users.remove_if(&id, |_, record| record.detail.is_deleted).is_none() {
+            return Ok(false);
+        }
+        self.refresh_tokens.retain(|_, token| token.user_id != id);
+        self.user_roles.retain(|(user_id, _)| *user_id != id);
+        Ok(true)
+    }
+
+    /// Permanently deletes every user record deleted before `before`, see `purge_user`.
+    async fn purge_users_deleted_before(&self, before: DateTime<Utc>) -> Result<u64> {
+        let expired: Vec<Uuid> = self
+            .users
+            .iter()
+            .filter(|record| record.detail.is_deleted && record.detail.deleted_at.is_some_and(|deleted_at| deleted_at < before))
+            .map(|record| record.detail.id)
+            .collect();
+        let mut purged = 0;
+        for id in expired {
+            if self.purge_user(id).await? {
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+
+    /// Changes the type of a live user.
+    async fn set_user_type(&self, id: Uuid, user_type: UserType) -> Result<bool> {
+        match self.users.get_mut(&id) {
+            Some(mut record) if !record.detail.is_deleted => {
+                record.detail.user_type = user_type;
+                touch(&mut record.detail);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn is_bootstrapped(&self) -> Result<bool> {
+        Ok(self.bootstrapped.load(Ordering::SeqCst))
+    }
+
+    /// In a transaction the mark is only written back on commit, which fails if another one marked it first.
+    async fn mark_bootstrapped(&self) -> Result<bool> {
+        if self.transaction.as_ref().is_some_and(|transaction| transaction.target.bootstrapped.load(Ordering::SeqCst)) {
+            return Ok(false);
+        }
+        Ok(!self.bootstrapped.swap(true, Ordering::SeqCst))
+    }
+}
+
+impl InMemoryDbImpl {
+    /// Mirrors the unique indexes of the SQL backends: usernames and, ignoring case, non-empty emails
+    /// are unique among live users. `except` is the user being written.
+    ///
+    /// Hold [`lock_user_writes`](Self::lock_user_writes) until the write is done.
+    fn check_user_conflicts(&self, username: &str, email: &str, except: Option<Uuid>) -> Result<()> {
+        match self.users.iter().filter(|record| Some(record.detail.id) != except).find_map(|record| conflict(username, email, &record.detail)) {
+            Some(field) => Err(Error::Conflict { field }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The unique field a live user with `username` and `email` would share with `other`, if any.
+pub(super) fn conflict(username: &str, email: &str, other: &UserDetail) -> Option<&'static str> {
+    if other.is_deleted {
+        None
+    } else if other.username == username {
+        Some("username")
+    } else if !email.is_empty() && other.email.to_lowercase() == email.to_lowercase() {
+        Some("email")
+    } else {
+        None
+    }
+}
+
+/// Records a change of the user's fields, like the SQL backends do in every such `UPDATE`.
+fn touch(detail: &mut UserDetail) {
+    detail.updated_at = Utc::now();
+    detail.version += 1;
+}
+
+/// Mirrors `filters::push_user_filter` of the SQL backends.
+fn matches(detail: &UserDetail, filter: &UserFilter) -> bool {
+    let contains = |value: &str, needle: &Option<String>| needle.as_deref().is_none_or(|needle| value.to_lowercase().contains(&needle.to_lowercase()));
+    let deleted = match filter.deleted {
+        DeletedUsers::Exclude => !detail.is_deleted,
+        DeletedUsers::Include => true,
+        DeletedUsers::Only => detail.is_deleted,
+    };
+    deleted
+        && contains(&detail.username, &filter.username)
+        && contains(&detail.alias, &filter.alias)
+        && contains(&detail.email, &filter.email)
+        && filter.user_type.is_none_or(|user_type| detail.user_type == user_type)
+        && filter.created_since.is_none_or(|since| detail.created_at >= since)
+        && filter.created_before.is_none_or(|before| detail.created_at < before)
+}
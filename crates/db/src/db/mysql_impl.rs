@@ -0,0 +1,28 @@
+use std::borrow::Cow;
+
+use crate::db::sql_impl::{Dialect, SqlDbImpl};
+use sqlx::{MySql, migrate::Migrator, mysql::MySqlQueryResult};
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations/mysql");
+
+/// MySQL/MariaDB storage. `Uuid` values are stored as `BINARY(16)`.
+pub type MySqlDbImpl = SqlDbImpl<MySql>;
+
+impl Dialect for MySql {
+    fn migrator() -> &'static Migrator {
+        &MIGRATOR
+    }
+
+    /// MySQL takes `?` placeholders as they are.
+    fn sql(sql: &str) -> Cow<'_, str> {
+        Cow::Borrowed(sql)
+    }
+
+    fn insert_ignore(target: &str) -> String {
+        format!("INSERT IGNORE INTO {target}")
+    }
+
+    fn rows_affected(result: &MySqlQueryResult) -> u64 {
+        result.rows_affected()
+    }
+}
@@ -0,0 +1,34 @@
+use std::borrow::Cow;
+
+use crate::db::sql_impl::{Dialect, SqlDbImpl};
+use sqlx::{Postgres, migrate::Migrator, postgres::PgQueryResult};
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+
+pub type PostgresDbImpl = SqlDbImpl<Postgres>;
+
+impl Dialect for Postgres {
+    fn migrator() -> &'static Migrator {
+        &MIGRATOR
+    }
+
+    /// Numbers the placeholders, `$1`, `$2` and so on.
+    fn sql(sql: &str) -> Cow<'_, str> {
+        let mut numbered = String::with_capacity(sql.len() + 16);
+        for (n, part) in sql.split('?').enumerate() {
+            if n > 0 {
+                numbered.push_str(&format!("${n}"));
+            }
+            numbered.push_str(part);
+        }
+        Cow::Owned(numbered)
+    }
+
+    fn insert_ignore(target: &str) -> String {
+        format!("INSERT INTO {target} ON CONFLICT DO NOTHING")
+    }
+
+    fn rows_affected(result: &PgQueryResult) -> u64 {
+        result.rows_affected()
+    }
+}
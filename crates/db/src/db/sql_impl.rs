@@ -0,0 +1,43 @@
+pub mod migrate_storage;
+pub mod permission_storage;
+pub mod token_storage;
+pub mod transaction_storage;
+pub mod user_storage;
+
+use std::borrow::Cow;
+
+use crate::{
+    Result,
+    conn::{Conn, SharedTransaction},
+};
+use sqlx::{Database, Pool, migrate::{Migrate, Migrator}, pool::PoolOptions};
+
+/// What the SQL of [`SqlDbImpl`] differs in between the server backends.
+pub trait Dialect: Database<Connection: Migrate> {
+    /// Migrations embedded for the backend.
+    fn migrator() -> &'static Migrator;
+    /// `sql` with its `?` placeholders in the syntax of the backend. Queries must not contain a literal `?`.
+    fn sql(sql: &str) -> Cow<'_, str>;
+    /// `INSERT` into `target`, e.g. `t (a, b) VALUES (?, ?)`, skipping rows that conflict with a unique key.
+    fn insert_ignore(target: &str) -> String;
+    fn rows_affected(result: &Self::QueryResult) -> u64;
+}
+
+/// Storage of the server backends, Postgres and MySQL, which only differ in their [`Dialect`].
+pub struct SqlDbImpl<DB: Dialect> {
+    pool: Pool<DB>,
+    /// Set on the handles `begin` returns.
+    transaction: Option<SharedTransaction<DB>>,
+}
+
+impl<DB: Dialect> SqlDbImpl<DB> {
+    pub async fn new(target: String) -> Result<Self> {
+        let pool = PoolOptions::<DB>::new().max_connections(10).connect(&target).await?;
+        Ok(SqlDbImpl { pool, transaction: None })
+    }
+
+    /// The connection for the next statement, see [`Conn`].
+    async fn conn(&self) -> Result<Conn<'_, DB>> {
+        Conn::acquire(&self.pool, &self.transaction).await
+    }
+}
@@ -0,0 +1,24 @@
+use crate::{
+    Result,
+    db::{MigrateDb, sql_impl::{Dialect, SqlDbImpl}},
+    migrate::{self, MigrationStatus, MigrationStep},
+};
+use async_trait::async_trait;
+
+#[async_trait]
+impl<DB: Dialect> MigrateDb for SqlDbImpl<DB> {
+    /// Lists the migrations embedded for the backend against the `_sqlx_migrations` table.
+    async fn migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        migrate::status(DB::migrator(), &self.pool).await
+    }
+
+    /// Applies pending migrations of the backend.
+    async fn migrate_up(&self, to: Option<i64>, dry_run: bool) -> Result<Vec<MigrationStep>> {
+        migrate::up(DB::migrator(), &self.pool, to, dry_run).await
+    }
+
+    /// Reverts applied migrations with their `.down.sql` counterpart.
+    async fn migrate_down(&self, to: Option<i64>, dry_run: bool) -> Result<Vec<MigrationStep>> {
+        migrate::down(DB::migrator(), &self.pool, to, dry_run).await
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{
+    Result,
+    db::{DbTransaction, FullDb, MigrateDb, PermissionDb, TokenDb, UserDb, sql_impl::{Dialect, SqlDbImpl}},
+};
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+#[async_trait]
+impl<DB: Dialect> FullDb for SqlDbImpl<DB>
+where
+    SqlDbImpl<DB>: DbTransaction + MigrateDb,
+{
+    /// Starts a transaction on a connection of its own, which the handle keeps until it is committed or dropped.
+    async fn begin(&self) -> Result<Box<dyn DbTransaction>> {
+        let transaction = self.pool.begin().await?;
+        Ok(Box::new(SqlDbImpl {
+            pool: self.pool.clone(),
+            transaction: Some(Mutex::new(transaction)),
+        }))
+    }
+}
+
+#[async_trait]
+impl<DB: Dialect> DbTransaction for SqlDbImpl<DB>
+where
+    SqlDbImpl<DB>: UserDb + TokenDb + PermissionDb,
+{
+    async fn commit(self: Box<Self>) -> Result<()> {
+        if let Some(transaction) = self.transaction {
+            transaction.into_inner().commit().await?;
+        }
+        Ok(())
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<()> {
+        if let Some(transaction) = self.transaction {
+            transaction.into_inner().rollback().await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,336 @@
+use crate::{
+    Error, Result, filters, search,
+    db::{UserDb, sql_impl::{Dialect, SqlDbImpl}},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use shared::models::{
+    Page, Pagination,
+    user::{UserDetail, UserDetailToAddOrUpdate, UserDetailToUpdate, UserFilter, UserPatch, UserSearchHit, UserSort, UserSortKey, UserType},
+};
+use sqlx::{Connection, Encode, Executor, FromRow, IntoArguments, QueryBuilder, Type};
+use uuid::Uuid;
+
+#[async_trait]
+impl<DB: Dialect> UserDb for SqlDbImpl<DB>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'a, 'q> DB::Arguments<'a>: IntoArguments<'q, DB>,
+    for<'q> Uuid: Encode<'q, DB> + Type<DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'q> Option<String>: Encode<'q, DB>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Type<DB>,
+    for<'q> Option<i64>: Encode<'q, DB>,
+    for<'q> DateTime<Utc>: Encode<'q, DB> + Type<DB>,
+    for<'q> UserType: Encode<'q, DB> + Type<DB>,
+    for<'r> UserDetail: FromRow<'r, DB::Row>,
+    for<'r> (i64,): FromRow<'r, DB::Row>,
+{
+    async fn exists_user_type(&self, user_type: UserType) -> Result<bool> {
+        let row = sqlx::query(&DB::sql(r#"SELECT 1 FROM users WHERE is_deleted = FALSE AND user_type = ? LIMIT 1"#))
+            .bind(user_type)
+            .fetch_optional(&mut *self.conn().await?)
+            .await?;
+        Ok(row.is_some())
+    }
+
+    /// Inserts a new user record into the 'users' table and returns the new ID.
+    async fn add_user(&self, user_type: UserType, detail: UserDetailToAddOrUpdate) -> Result<Uuid> {
+        let id = Uuid::now_v7();
+        sqlx::query(&DB::sql(
+            r#"
+            INSERT INTO users (id, alias, username, password, email, user_type)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        ))
+        .bind(id)
+        .bind(detail.alias)
+        .bind(detail.username)
+        .bind(detail.password)
+        .bind(detail.email)
+        .bind(user_type)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Soft deletes a user record by ID in the 'users' table, recording when.
+    async fn remove_user(&self, id: Uuid) -> Result<bool> {
+        let now = Utc::now();
+        let result = sqlx::query(&DB::sql(
+            r#"
+            UPDATE users SET is_deleted = TRUE, deleted_at = ?, updated_at = ?, version = version + 1
+            WHERE id = ? AND is_deleted = FALSE
+            "#,
+        ))
+        .bind(now)
+        .bind(now)
+        .bind(id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(DB::rows_affected(&result) > 0)
+    }
+
+    /// Fetches a single user record by ID from the 'users' table.
+    async fn get_user(&self, id: Uuid) -> Result<Option<UserDetail>> {
+        // NOTE: SELECT fields MUST match the UserDetail struct fields exactly
+        let detail = sqlx::query_as::<_, UserDetail>(&DB::sql(
+            r#"
+            SELECT id, user_type, alias, username, password, email, created_at, updated_at, tokens_valid_after, is_deleted, deleted_at, version
+            FROM users
+            WHERE id = ? AND is_deleted = FALSE
+            "#,
+        ))
+        .bind(id)
+        .fetch_optional(&mut *self.conn().await?)
+        .await?;
+
+        Ok(detail)
+    }
+
+    /// Fetches a single live user record by username from the 'users' table.
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<UserDetail>> {
+        // NOTE: SELECT fields MUST match the UserDetail struct fields exactly
+        let detail = sqlx::query_as::<_, UserDetail>(&DB::sql(
+            r#"
+            SELECT id, user_type, alias, username, password, email, created_at, updated_at, tokens_valid_after, is_deleted, deleted_at, version
+            FROM users
+            WHERE username = ? AND is_deleted = FALSE
+            "#,
+        ))
+        .bind(username)
+        .fetch_optional(&mut *self.conn().await?)
+        .await?;
+
+        Ok(detail)
+    }
+
+    /// Searches live user records by alias, username and email. Without a full-text index the users are matched with
+    /// `LIKE` and ranked in SQL by the score of [`search::rank`], which then highlights the snippets of the best ones.
+    async fn search_users(&self, query: &str, limit: i64) -> Result<Vec<UserSearchHit<UserDetail>>> {
+        let terms = search::terms(query);
+        // NOTE: SELECT fields MUST match the UserDetail struct fields exactly
+        let mut best = QueryBuilder::<DB>::new("SELECT id, user_type, alias, username, password, email, created_at, updated_at, tokens_valid_after, is_deleted, deleted_at, version FROM users");
+        filters::push_user_filter::<_, DateTime<Utc>>(&mut best, &UserFilter::default());
+        filters::push_user_search(&mut best, &terms);
+        filters::push_user_search_order(&mut best, &terms);
+        best.push(" LIMIT ").push_bind(limit.max(0));
+        let best = best.build_query_as::<UserDetail>().fetch_all(&mut *self.conn().await?).await?;
+
+        Ok(search::rank(best, &terms, limit.max(0) as usize))
+    }
+
+    /// Fetches a page of user records from the 'users' table matching `filter`.
+    async fn get_user_list(&self, pagination: Pagination, sort: UserSort, filter: UserFilter) -> Result<Page<UserDetail>> {
+        let mut count = QueryBuilder::<DB>::new("SELECT COUNT(*) FROM users");
+        filters::push_user_filter::<_, DateTime<Utc>>(&mut count, &filter);
+        let total: i64 = count.build_query_scalar().fetch_one(&mut *self.conn().await?).await?;
+
+        // One more row than requested tells whether there is a next page.
+        let (offset, limit) = pagination.offset_limit();
+        // MySQL has no `LIMIT ALL`, so an unlimited page uses the largest limit on every backend.
+        let limit = limit.map_or(i64::MAX, |limit| limit.saturating_add(1));
+
+        // NOTE: SELECT fields MUST match the UserDetail struct fields exactly
+        let mut query = QueryBuilder::<DB>::new("SELECT id, user_type, alias, username, password, email, created_at, updated_at, tokens_valid_after, is_deleted, deleted_at, version FROM users");
+        filters::push_user_filter::<_, DateTime<Utc>>(&mut query, &filter);
+        filters::push_user_order(&mut query, &pagination, sort);
+        query.push(" LIMIT ").push_bind(limit).push(" OFFSET ").push_bind(offset);
+        let list = query.build_query_as::<UserDetail>().fetch_all(&mut *self.conn().await?).await?;
+
+        Ok(Page::new(list, total, &pagination, |user| (sort.sort == UserSortKey::Id).then_some(user.id)))
+    }
+
+    /// Updates an existing user record in the 'users' table by ID, if it is at `version` when given.
+    async fn update_user(&self, id: Uuid, detail: UserDetailToUpdate, version: Option<i64>) -> Result<bool> {
+        let result = sqlx::query(&DB::sql(
+            r#"
+            UPDATE users
+            SET alias = ?, username = ?, email = ?, updated_at = ?, version = version + 1
+            WHERE id = ? AND is_deleted = FALSE AND version = COALESCE(?, version)
+            "#,
+        ))
+        .bind(detail.alias)
+        .bind(detail.username)
+        .bind(detail.email)
+        .bind(Utc::now())
+        .bind(id)
+        .bind(version)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        match version {
+            Some(expected) if DB::rows_affected(&result) == 0 && self.get_user(id).await?.is_some() => Err(Error::VersionMismatch { expected }),
+            _ => Ok(DB::rows_affected(&result) > 0),
+        }
+    }
+
+    /// Updates the fields `patch` sets of a live user, leaving the others untouched.
+    async fn patch_user(&self, id: Uuid, patch: UserPatch) -> Result<bool> {
+        let result = sqlx::query(&DB::sql(
+            r#"
+            UPDATE users
+            SET alias = COALESCE(?, alias), username = COALESCE(?, username), updated_at = ?, version = version + 1
+            WHERE id = ? AND is_deleted = FALSE
+            "#,
+        ))
+        .bind(patch.alias)
+        .bind(patch.username)
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(DB::rows_affected(&result) > 0)
+    }
+
+    /// Replaces the stored password hash of an existing user.
+    async fn update_user_password(&self, id: Uuid, password: &str) -> Result<bool> {
+        let result = sqlx::query(&DB::sql(
+            r#"
+            UPDATE users
+            SET password = ?, updated_at = ?, version = version + 1
+            WHERE id = ? AND is_deleted = FALSE
+            "#,
+        ))
+        .bind(password)
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(DB::rows_affected(&result) > 0)
+    }
+
+    /// Swaps the stored password hash of a live user for an equivalent one.
+    async fn rehash_user_password(&self, id: Uuid, old: &str, new: &str) -> Result<bool> {
+        let result = sqlx::query(&DB::sql(
+            r#"
+            UPDATE users
+            SET password = ?
+            WHERE id = ? AND password = ? AND is_deleted = FALSE
+            "#,
+        ))
+        .bind(new)
+        .bind(id)
+        .bind(old)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(DB::rows_affected(&result) > 0)
+    }
+
+    /// Replaces the email address of a live user.
+    async fn update_user_email(&self, id: Uuid, email: &str) -> Result<bool> {
+        let result = sqlx::query(&DB::sql(
+            r#"
+            UPDATE users
+            SET email = ?, updated_at = ?, version = version + 1
+            WHERE id = ? AND is_deleted = FALSE
+            "#,
+        ))
+        .bind(email)
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(DB::rows_affected(&result) > 0)
+    }
+
+    /// Rejects every access token of the user issued at or before `at`.
+    async fn set_user_tokens_valid_after(&self, id: Uuid, at: DateTime<Utc>) -> Result<bool> {
+        let result = sqlx::query(&DB::sql(
+            r#"
+            UPDATE users
+            SET tokens_valid_after = ?
+            WHERE id = ? AND is_deleted = FALSE
+            "#,
+        ))
+        .bind(at)
+        .bind(id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(DB::rows_affected(&result) > 0)
+    }
+
+    /// Undoes `remove_user`, making a deleted user live again.
+    async fn restore_user(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(&DB::sql(
+            r#"
+            UPDATE users SET is_deleted = FALSE, deleted_at = NULL, updated_at = ?, version = version + 1
+            WHERE id = ? AND is_deleted = TRUE
+            "#,
+        ))
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(DB::rows_affected(&result) > 0)
+    }
+
+    /// Permanently deletes a deleted user record together with its refresh tokens and role assignments.
+    async fn purge_user(&self, id: Uuid) -> Result<bool> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        let result = sqlx::query(&DB::sql(r#"DELETE FROM users WHERE id = ? AND is_deleted = TRUE"#)).bind(id).execute(&mut *tx).await?;
+        if DB::rows_affected(&result) == 0 {
+            return Ok(false);
+        }
+        sqlx::query(&DB::sql(r#"DELETE FROM refresh_tokens WHERE user_id = ?"#)).bind(id).execute(&mut *tx).await?;
+        sqlx::query(&DB::sql(r#"DELETE FROM user_roles WHERE user_id = ?"#)).bind(id).execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Permanently deletes every user record deleted before `before`, see `purge_user`.
+    async fn purge_users_deleted_before(&self, before: DateTime<Utc>) -> Result<u64> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query(&DB::sql(r#"DELETE FROM refresh_tokens WHERE user_id IN (SELECT id FROM users WHERE is_deleted = TRUE AND deleted_at < ?)"#)).bind(before).execute(&mut *tx).await?;
+        sqlx::query(&DB::sql(r#"DELETE FROM user_roles WHERE user_id IN (SELECT id FROM users WHERE is_deleted = TRUE AND deleted_at < ?)"#)).bind(before).execute(&mut *tx).await?;
+        let result = sqlx::query(&DB::sql(r#"DELETE FROM users WHERE is_deleted = TRUE AND deleted_at < ?"#)).bind(before).execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(DB::rows_affected(&result))
+    }
+
+    /// Changes the type of a live user.
+    async fn set_user_type(&self, id: Uuid, user_type: UserType) -> Result<bool> {
+        let result = sqlx::query(&DB::sql(
+            r#"
+            UPDATE users
+            SET user_type = ?, updated_at = ?, version = version + 1
+            WHERE id = ? AND is_deleted = FALSE
+            "#,
+        ))
+        .bind(user_type)
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(DB::rows_affected(&result) > 0)
+    }
+
+    async fn is_bootstrapped(&self) -> Result<bool> {
+        let row = sqlx::query(&DB::sql(r#"SELECT 1 FROM bootstrap LIMIT 1"#)).fetch_optional(&mut *self.conn().await?).await?;
+        Ok(row.is_some())
+    }
+
+    /// Inserts the single row of the 'bootstrap' table, its primary key makes a second insert do nothing.
+    async fn mark_bootstrapped(&self) -> Result<bool> {
+        let result = sqlx::query(&DB::sql(&DB::insert_ignore(r#"bootstrap (id, bootstrapped_at) VALUES (1, ?)"#)))
+            .bind(Utc::now())
+            .execute(&mut *self.conn().await?)
+            .await?;
+
+        Ok(DB::rows_affected(&result) > 0)
+    }
+}
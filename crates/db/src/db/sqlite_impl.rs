@@ -1,21 +1,71 @@
+pub mod migrate_storage;
+pub mod permission_storage;
+pub mod token_storage;
+pub mod transaction_storage;
 pub mod user_storage;
 
-use crate::Result;
+use std::str::FromStr;
+
+use crate::{
+    Result,
+    conn::{Conn, SharedTransaction},
+};
+use chrono::{DateTime, Utc};
 use sqlx::{
-    SqlitePool,
-    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    Encode, Sqlite, SqlitePool, Type,
+    encode::IsNull,
+    error::BoxDynError,
+    migrate::Migrator,
+    sqlite::{SqliteArgumentValue, SqliteConnectOptions, SqlitePoolOptions, SqliteTypeInfo},
 };
 
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
 pub struct SqliteDbImpl {
     pool: SqlitePool,
+    /// Set on the handles `begin` returns.
+    transaction: Option<SharedTransaction<Sqlite>>,
 }
 
 impl SqliteDbImpl {
     pub async fn new(target: String) -> Result<Self> {
-        let target = target.replace("sqlite:", "");
-        let options = SqliteConnectOptions::new().filename(target).create_if_missing(true);
+        // Parsed as a URL, so `sqlite::memory:` is one database shared by the pool rather than one per connection.
+        let options = SqliteConnectOptions::from_str(&target)?.create_if_missing(true);
         let pool = SqlitePoolOptions::new().max_connections(6).connect_with(options).await?;
-        sqlx::migrate!("./migrations/sqlite").run(&pool).await?;
-        Ok(SqliteDbImpl { pool })
+        Ok(SqliteDbImpl { pool, transaction: None })
+    }
+
+    /// The connection for the next statement, see [`Conn`].
+    async fn conn(&self) -> Result<Conn<'_, Sqlite>> {
+        Conn::acquire(&self.pool, &self.transaction).await
+    }
+}
+
+/// A time as stored in SQLite, the text `strftime('%Y-%m-%dT%H:%M:%fZ')` gives. Stored times are compared as text,
+/// which only orders them right if all of them share this one format.
+#[derive(Clone, Copy)]
+pub(crate) struct Timestamp(DateTime<Utc>);
+
+impl Timestamp {
+    pub(crate) fn now() -> Self {
+        Self(Utc::now())
+    }
+}
+
+impl From<DateTime<Utc>> for Timestamp {
+    fn from(at: DateTime<Utc>) -> Self {
+        Self(at)
+    }
+}
+
+impl Type<Sqlite> for Timestamp {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for Timestamp {
+    fn encode_by_ref(&self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> std::result::Result<IsNull, BoxDynError> {
+        Encode::<Sqlite>::encode(self.0.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(), buf)
     }
 }
@@ -0,0 +1,24 @@
+use crate::{
+    Result,
+    db::{MigrateDb, sqlite_impl::{MIGRATOR, SqliteDbImpl}},
+    migrate::{self, MigrationStatus, MigrationStep},
+};
+use async_trait::async_trait;
+
+#[async_trait]
+impl MigrateDb for SqliteDbImpl {
+    /// Lists the migrations embedded from `migrations/sqlite` against the `_sqlx_migrations` table.
+    async fn migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        migrate::status(&MIGRATOR, &self.pool).await
+    }
+
+    /// Applies pending migrations from `migrations/sqlite`.
+    async fn migrate_up(&self, to: Option<i64>, dry_run: bool) -> Result<Vec<MigrationStep>> {
+        migrate::up(&MIGRATOR, &self.pool, to, dry_run).await
+    }
+
+    /// Reverts applied migrations with their `.down.sql` counterpart.
+    async fn migrate_down(&self, to: Option<i64>, dry_run: bool) -> Result<Vec<MigrationStep>> {
+        migrate::down(&MIGRATOR, &self.pool, to, dry_run).await
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{
+    Result,
+    db::{PermissionDb, parse_permissions, sqlite_impl::{SqliteDbImpl, Timestamp}},
+};
+use async_trait::async_trait;
+use shared::models::permission::{Permission, Role, RoleToAddOrUpdate};
+use sqlx::{Connection, Sqlite, Transaction};
+use uuid::Uuid;
+
+#[async_trait]
+impl PermissionDb for SqliteDbImpl {
+    /// Inserts a new role and its permissions into the 'roles' and 'role_permissions' tables and returns the new ID.
+    async fn add_role(&self, role: RoleToAddOrUpdate) -> Result<Uuid> {
+        let id = Uuid::now_v7();
+        let now = Timestamp::now();
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO roles (id, name, description, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(id)
+        .bind(role.name)
+        .bind(role.description)
+        .bind(now)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        insert_role_permissions(&mut tx, id, &role.permissions).await?;
+        tx.commit().await?;
+
+        Ok(id)
+    }
+
+    /// Updates an existing role by ID and replaces its permissions.
+    async fn update_role(&self, id: Uuid, role: RoleToAddOrUpdate) -> Result<bool> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE roles
+            SET name = ?, description = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(role.name)
+        .bind(role.description)
+        .bind(Timestamp::now())
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query(r#"DELETE FROM role_permissions WHERE role_id = ?"#).bind(id).execute(&mut *tx).await?;
+        insert_role_permissions(&mut tx, id, &role.permissions).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Deletes a role by ID together with its permissions and assignments.
+    async fn remove_role(&self, id: Uuid) -> Result<bool> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query(r#"DELETE FROM user_roles WHERE role_id = ?"#).bind(id).execute(&mut *tx).await?;
+        sqlx::query(r#"DELETE FROM role_permissions WHERE role_id = ?"#).bind(id).execute(&mut *tx).await?;
+        let result = sqlx::query(r#"DELETE FROM roles WHERE id = ?"#).bind(id).execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Fetches a single role by ID from the 'roles' table.
+    async fn get_role(&self, id: Uuid) -> Result<Option<Role>> {
+        // NOTE: SELECT fields MUST match the Role struct fields exactly
+        let role = sqlx::query_as::<_, Role>(
+            r#"
+            SELECT id, name, description, created_at, updated_at
+            FROM roles
+            WHERE id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *self.conn().await?)
+        .await?;
+
+        match role {
+            Some(mut role) => {
+                role.permissions = self.get_role_permissions(role.id).await?;
+                Ok(Some(role))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Fetches every role from the 'roles' table.
+    async fn get_role_list(&self) -> Result<Vec<Role>> {
+        // NOTE: SELECT fields MUST match the Role struct fields exactly
+        let mut list = sqlx::query_as::<_, Role>(
+            r#"
+            SELECT id, name, description, created_at, updated_at
+            FROM roles
+            ORDER BY name
+            "#,
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+
+        for role in list.iter_mut() {
+            role.permissions = self.get_role_permissions(role.id).await?;
+        }
+        Ok(list)
+    }
+
+    /// Assigns a role to a user. Returns `false` if the user already had it.
+    async fn assign_role(&self, user_id: Uuid, role_id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO user_roles (user_id, role_id)
+            VALUES (?, ?)
+            ON CONFLICT (user_id, role_id) DO NOTHING
+            "#,
+        )
+        .bind(user_id)
+        .bind(role_id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Removes a role from a user.
+    async fn unassign_role(&self, user_id: Uuid, role_id: Uuid) -> Result<bool> {
+        let result = sqlx::query(r#"DELETE FROM user_roles WHERE user_id = ? AND role_id = ?"#)
+            .bind(user_id)
+            .bind(role_id)
+            .execute(&mut *self.conn().await?)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Fetches every role assigned to a user.
+    async fn get_user_roles(&self, user_id: Uuid) -> Result<Vec<Role>> {
+        // NOTE: SELECT fields MUST match the Role struct fields exactly
+        let mut list = sqlx::query_as::<_, Role>(
+            r#"
+            SELECT r.id, r.name, r.description, r.created_at, r.updated_at
+            FROM roles r
+            JOIN user_roles ur ON ur.role_id = r.id
+            WHERE ur.user_id = ?
+            ORDER BY r.name
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+
+        for role in list.iter_mut() {
+            role.permissions = self.get_role_permissions(role.id).await?;
+        }
+        Ok(list)
+    }
+
+    /// Fetches the IDs of every user a role is assigned to.
+    async fn get_role_user_ids(&self, role_id: Uuid) -> Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar::<_, Uuid>(r#"SELECT user_id FROM user_roles WHERE role_id = ?"#)
+            .bind(role_id)
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(ids)
+    }
+
+    /// Union of the permissions of every role assigned to the user.
+    async fn get_user_permissions(&self, user_id: Uuid) -> Result<Vec<Permission>> {
+        let permissions = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT DISTINCT rp.permission
+            FROM role_permissions rp
+            JOIN user_roles ur ON ur.role_id = rp.role_id
+            WHERE ur.user_id = ?
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+
+        Ok(parse_permissions(permissions))
+    }
+}
+
+impl SqliteDbImpl {
+    async fn get_role_permissions(&self, role_id: Uuid) -> Result<Vec<Permission>> {
+        let permissions = sqlx::query_scalar::<_, String>(r#"SELECT permission FROM role_permissions WHERE role_id = ?"#)
+            .bind(role_id)
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(parse_permissions(permissions))
+    }
+}
+
+async fn insert_role_permissions(tx: &mut Transaction<'_, Sqlite>, role_id: Uuid, permissions: &[Permission]) -> Result<()> {
+    for permission in permissions {
+        sqlx::query(
+            r#"
+            INSERT INTO role_permissions (role_id, permission)
+            VALUES (?, ?)
+            ON CONFLICT (role_id, permission) DO NOTHING
+            "#,
+        )
+        .bind(role_id)
+        .bind(permission.as_str())
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use crate::{
+    Result,
+    db::{TokenDb, sqlite_impl::{SqliteDbImpl, Timestamp}},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use shared::models::token::RefreshToken;
+use uuid::Uuid;
+
+#[async_trait]
+impl TokenDb for SqliteDbImpl {
+    /// Inserts a new refresh token record into the 'refresh_tokens' table and returns the new ID.
+    async fn add_refresh_token(&self, user_id: Uuid, family_id: Uuid, token_hash: &str, expires_at: DateTime<Utc>) -> Result<Uuid> {
+        let id = Uuid::now_v7();
+        sqlx::query(
+            r#"
+            INSERT INTO refresh_tokens (id, user_id, family_id, token_hash, expires_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(family_id)
+        .bind(token_hash)
+        .bind(Timestamp::from(expires_at))
+        .bind(Timestamp::now())
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Fetches a refresh token record by the hash of the token.
+    async fn get_refresh_token(&self, token_hash: &str) -> Result<Option<RefreshToken>> {
+        // NOTE: SELECT fields MUST match the RefreshToken struct fields exactly
+        let token = sqlx::query_as::<_, RefreshToken>(
+            r#"
+            SELECT id, user_id, family_id, token_hash, expires_at, used_at, revoked, created_at
+            FROM refresh_tokens
+            WHERE token_hash = ?
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut *self.conn().await?)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Marks an unused, unrevoked refresh token as used.
+    async fn use_refresh_token(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE refresh_tokens SET used_at = ?
+            WHERE id = ? AND used_at IS NULL AND revoked = 0
+            "#,
+        )
+        .bind(Timestamp::now())
+        .bind(id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Revokes every refresh token descending from the same login.
+    async fn revoke_refresh_token_family(&self, family_id: Uuid) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE refresh_tokens SET revoked = TRUE
+            WHERE family_id = ? AND revoked = 0
+            "#,
+        )
+        .bind(family_id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Revokes every refresh token of a user.
+    async fn revoke_user_refresh_tokens(&self, user_id: Uuid) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE refresh_tokens SET revoked = TRUE
+            WHERE user_id = ? AND revoked = 0
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Inserts a revoked access token into the 'revoked_tokens' table, dropping entries that have expired meanwhile.
+    async fn revoke_access_token(&self, jti: Uuid, expires_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query(r#"DELETE FROM revoked_tokens WHERE expires_at < ?"#).bind(Timestamp::now()).execute(&mut *self.conn().await?).await?;
+        sqlx::query(
+            r#"
+            INSERT INTO revoked_tokens (jti, expires_at)
+            VALUES (?, ?)
+            ON CONFLICT (jti) DO NOTHING
+            "#,
+        )
+        .bind(jti)
+        .bind(Timestamp::from(expires_at))
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn is_access_token_revoked(&self, jti: Uuid) -> Result<bool> {
+        let row = sqlx::query(r#"SELECT 1 FROM revoked_tokens WHERE jti = ? LIMIT 1"#)
+            .bind(jti)
+            .fetch_optional(&mut *self.conn().await?)
+            .await?;
+        Ok(row.is_some())
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{
+    Result,
+    db::{DbTransaction, FullDb, sqlite_impl::SqliteDbImpl},
+};
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+#[async_trait]
+impl FullDb for SqliteDbImpl {
+    /// Starts a transaction on a connection of its own, which the handle keeps until it is committed or dropped.
+    async fn begin(&self) -> Result<Box<dyn DbTransaction>> {
+        let transaction = self.pool.begin().await?;
+        Ok(Box::new(SqliteDbImpl {
+            pool: self.pool.clone(),
+            transaction: Some(Mutex::new(transaction)),
+        }))
+    }
+}
+
+#[async_trait]
+impl DbTransaction for SqliteDbImpl {
+    async fn commit(self: Box<Self>) -> Result<()> {
+        if let Some(transaction) = self.transaction {
+            transaction.into_inner().commit().await?;
+        }
+        Ok(())
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<()> {
+        if let Some(transaction) = self.transaction {
+            transaction.into_inner().rollback().await?;
+        }
+        Ok(())
+    }
+}
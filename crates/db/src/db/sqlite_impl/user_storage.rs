@@ -1,12 +1,14 @@
 use crate::{
-    Result,
-    db::{UserDb, sqlite_impl::SqliteDbImpl},
+    Error, Result, filters, search,
+    db::{UserDb, sqlite_impl::{SqliteDbImpl, Timestamp}},
 };
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use shared::models::{
-    Pagination,
-    user::{UserDetail, UserDetailToAddOrUpdate, UserType},
+    Page, Pagination,
+    user::{UserDetail, UserDetailToAddOrUpdate, UserDetailToUpdate, UserFilter, UserPatch, UserSearchHit, UserSort, UserSortKey, UserType},
 };
+use sqlx::{Connection, FromRow, QueryBuilder, Sqlite};
 use uuid::Uuid;
 
 #[async_trait]
@@ -14,7 +16,7 @@ impl UserDb for SqliteDbImpl {
     async fn exists_user_type(&self, user_type: UserType) -> Result<bool> {
         let row = sqlx::query(r#"SELECT 1 FROM users WHERE is_deleted = FALSE AND user_type = ? LIMIT 1"#)
             .bind(user_type)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&mut *self.conn().await?)
             .await?;
         Ok(row.is_some())
     }
@@ -22,10 +24,12 @@ impl UserDb for SqliteDbImpl {
     /// Inserts a new user record into the 'users' table and returns the new ID.
     async fn add_user(&self, user_type: UserType, detail: UserDetailToAddOrUpdate) -> Result<Uuid> {
         let id = Uuid::now_v7();
+        let now = Timestamp::now();
+        // Both timestamps are bound, the init migration's default for `updated_at` repeats the minutes as seconds.
         let query = sqlx::query(
             r#"
-            INSERT INTO users (id, alias, username, password, email, user_type)
-            VALUES (?, ?, ?, ?, ?, ?)
+            INSERT INTO users (id, alias, username, password, email, user_type, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(id)
@@ -33,22 +37,27 @@ impl UserDb for SqliteDbImpl {
         .bind(detail.username)
         .bind(detail.password)
         .bind(detail.email)
-        .bind(user_type);
+        .bind(user_type)
+        .bind(now)
+        .bind(now);
 
-        query.execute(&self.pool).await?;
+        query.execute(&mut *self.conn().await?).await?;
         Ok(id)
     }
 
-    /// Deletes a user record by ID from the 'users' table.
+    /// Soft deletes a user record by ID in the 'users' table, recording when.
     async fn remove_user(&self, id: Uuid) -> Result<bool> {
+        let now = Timestamp::now();
         let result = sqlx::query(
             r#"
-            UPDATE users SET is_deleted = TRUE
+            UPDATE users SET is_deleted = TRUE, deleted_at = ?, updated_at = ?, version = version + 1
             WHERE id = ? AND is_deleted = 0
             "#,
         )
+        .bind(now)
+        .bind(now)
         .bind(id)
-        .execute(&self.pool)
+        .execute(&mut *self.conn().await?)
         .await?;
 
         Ok(result.rows_affected() > 0)
@@ -59,74 +68,288 @@ impl UserDb for SqliteDbImpl {
         // NOTE: SELECT fields MUST match the UserDetail struct fields exactly
         let detail = sqlx::query_as::<_, UserDetail>(
             r#"
-            SELECT id, user_type, alias, username, password, email, created_at, updated_at
+            SELECT id, user_type, alias, username, password, email, created_at, updated_at, tokens_valid_after, is_deleted, deleted_at, version
             FROM users
             WHERE id = ? AND is_deleted = 0
             "#,
         )
         .bind(id)
-        .fetch_optional(&self.pool)
+        .fetch_optional(&mut *self.conn().await?)
         .await?;
 
         Ok(detail)
     }
 
-    async fn get_user_by_validate(&self, username: &str, password: &str) -> Result<Option<UserDetail>> {
+    /// Fetches a single live user record by username from the 'users' table.
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<UserDetail>> {
         // NOTE: SELECT fields MUST match the UserDetail struct fields exactly
         let detail = sqlx::query_as::<_, UserDetail>(
             r#"
-            SELECT id, user_type, alias, username, password, email, created_at, updated_at
+            SELECT id, user_type, alias, username, password, email, created_at, updated_at, tokens_valid_after, is_deleted, deleted_at, version
             FROM users
-            WHERE username = ? AND password = ? AND is_deleted = 0
+            WHERE username = ? AND is_deleted = 0
             "#,
         )
         .bind(username)
-        .bind(password)
-        .fetch_optional(&self.pool)
+        .fetch_optional(&mut *self.conn().await?)
         .await?;
 
         Ok(detail)
     }
 
-    /// Fetches a paginated list of user records from the 'users' table.
-    async fn get_user_list(&self, pagination: Pagination) -> Result<Vec<UserDetail>> {
-        let (page, size) = if pagination.is_unlimited() { (0, i64::MAX) } else { pagination.get_safety() };
-
-        // NOTE: SELECT fields MUST match the UserDetail struct fields exactly
-        let list = sqlx::query_as::<_, UserDetail>(
+    /// Searches live user records through the `users_fts` trigram index, ranked by BM25.
+    async fn search_users(&self, query: &str, limit: i64) -> Result<Vec<UserSearchHit<UserDetail>>> {
+        // Column weights follow `users_fts (id, alias, username, email)`, a username match counts the most.
+        let rows = sqlx::query_as::<_, SearchRow>(
             r#"
-            SELECT id, user_type, alias, username, password, email, created_at, updated_at
-            FROM users
-            WHERE is_deleted = 0
-            LIMIT ? OFFSET ?
+            SELECT u.id, u.user_type, u.alias, u.username, u.password, u.email, u.created_at, u.updated_at, u.tokens_valid_after, u.is_deleted, u.deleted_at, u.version,
+                -bm25(users_fts, 0.0, 2.0, 3.0, 1.0) AS score,
+                snippet(users_fts, -1, ?, ?, '…', 16) AS snippet
+            FROM users_fts
+            JOIN users u ON u.id = users_fts.id
+            WHERE users_fts MATCH ? AND u.is_deleted = FALSE
+            ORDER BY score DESC, u.username
+            LIMIT ?
             "#,
         )
-        .bind(size)
-        .bind(page - 1)
-        .fetch_all(&self.pool)
+        .bind(search::MARK_START)
+        .bind(search::MARK_END)
+        .bind(search::fts5_query(&search::terms(query)))
+        .bind(limit)
+        .fetch_all(&mut *self.conn().await?)
         .await?;
 
-        Ok(list)
+        Ok(rows
+            .into_iter()
+            .map(|row| UserSearchHit {
+                user: row.user,
+                score: row.score,
+                snippet: row.snippet,
+            })
+            .collect())
     }
 
-    /// Updates an existing user record in the 'users' table by ID.
-    async fn update_user(&self, id: Uuid, detail: UserDetailToAddOrUpdate) -> Result<bool> {
-        // Update all fields from UserDetailToAddOrUpdate
+    /// Fetches a page of user records from the 'users' table matching `filter`.
+    async fn get_user_list(&self, pagination: Pagination, sort: UserSort, filter: UserFilter) -> Result<Page<UserDetail>> {
+        let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM users");
+        filters::push_user_filter::<_, Timestamp>(&mut count, &filter);
+        let total: i64 = count.build_query_scalar().fetch_one(&mut *self.conn().await?).await?;
+
+        // One more row than requested tells whether there is a next page.
+        let (offset, limit) = pagination.offset_limit();
+        let limit = limit.map_or(i64::MAX, |limit| limit.saturating_add(1));
+
+        // NOTE: SELECT fields MUST match the UserDetail struct fields exactly
+        let mut query = QueryBuilder::<Sqlite>::new("SELECT id, user_type, alias, username, password, email, created_at, updated_at, tokens_valid_after, is_deleted, deleted_at, version FROM users");
+        filters::push_user_filter::<_, Timestamp>(&mut query, &filter);
+        filters::push_user_order(&mut query, &pagination, sort);
+        query.push(" LIMIT ").push_bind(limit).push(" OFFSET ").push_bind(offset);
+        let list = query.build_query_as::<UserDetail>().fetch_all(&mut *self.conn().await?).await?;
+
+        Ok(Page::new(list, total, &pagination, |user| (sort.sort == UserSortKey::Id).then_some(user.id)))
+    }
+
+    /// Updates an existing user record in the 'users' table by ID, if it is at `version` when given.
+    async fn update_user(&self, id: Uuid, detail: UserDetailToUpdate, version: Option<i64>) -> Result<bool> {
         let result = sqlx::query(
             r#"
             UPDATE users
-            SET alias = ?, username = ?, password = ?, email = ?
-            WHERE id = ? AND is_deleted = 0
+            SET alias = ?, username = ?, email = ?, updated_at = ?, version = version + 1
+            WHERE id = ? AND is_deleted = 0 AND version = COALESCE(?, version)
             "#,
         )
         .bind(detail.alias)
         .bind(detail.username)
-        .bind(detail.password)
         .bind(detail.email)
+        .bind(Timestamp::now())
         .bind(id)
-        .execute(&self.pool)
+        .bind(version)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        match version {
+            Some(expected) if result.rows_affected() == 0 && self.get_user(id).await?.is_some() => Err(Error::VersionMismatch { expected }),
+            _ => Ok(result.rows_affected() > 0),
+        }
+    }
+
+    /// Updates the fields `patch` sets of a live user, leaving the others untouched.
+    async fn patch_user(&self, id: Uuid, patch: UserPatch) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET alias = COALESCE(?, alias), username = COALESCE(?, username), updated_at = ?, version = version + 1
+            WHERE id = ? AND is_deleted = 0
+            "#,
+        )
+        .bind(patch.alias)
+        .bind(patch.username)
+        .bind(Timestamp::now())
+        .bind(id)
+        .execute(&mut *self.conn().await?)
         .await?;
 
         Ok(result.rows_affected() > 0)
     }
+
+    /// Replaces the stored password hash of an existing user.
+    async fn update_user_password(&self, id: Uuid, password: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET password = ?, updated_at = ?, version = version + 1
+            WHERE id = ? AND is_deleted = 0
+            "#,
+        )
+        .bind(password)
+        .bind(Timestamp::now())
+        .bind(id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Swaps the stored password hash of a live user for an equivalent one.
+    async fn rehash_user_password(&self, id: Uuid, old: &str, new: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET password = ?
+            WHERE id = ? AND password = ? AND is_deleted = 0
+            "#,
+        )
+        .bind(new)
+        .bind(id)
+        .bind(old)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Replaces the email address of a live user.
+    async fn update_user_email(&self, id: Uuid, email: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET email = ?, updated_at = ?, version = version + 1
+            WHERE id = ? AND is_deleted = 0
+            "#,
+        )
+        .bind(email)
+        .bind(Timestamp::now())
+        .bind(id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Rejects every access token of the user issued at or before `at`.
+    async fn set_user_tokens_valid_after(&self, id: Uuid, at: DateTime<Utc>) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET tokens_valid_after = ?
+            WHERE id = ? AND is_deleted = 0
+            "#,
+        )
+        .bind(Timestamp::from(at))
+        .bind(id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Undoes `remove_user`, making a deleted user live again.
+    async fn restore_user(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users SET is_deleted = FALSE, deleted_at = NULL, updated_at = ?, version = version + 1
+            WHERE id = ? AND is_deleted = TRUE
+            "#,
+        )
+        .bind(Timestamp::now())
+        .bind(id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Permanently deletes a deleted user record together with its refresh tokens and role assignments.
+    async fn purge_user(&self, id: Uuid) -> Result<bool> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        let result = sqlx::query(r#"DELETE FROM users WHERE id = ? AND is_deleted = TRUE"#).bind(id).execute(&mut *tx).await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        sqlx::query(r#"DELETE FROM refresh_tokens WHERE user_id = ?"#).bind(id).execute(&mut *tx).await?;
+        sqlx::query(r#"DELETE FROM user_roles WHERE user_id = ?"#).bind(id).execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Permanently deletes every user record deleted before `before`, see `purge_user`.
+    async fn purge_users_deleted_before(&self, before: DateTime<Utc>) -> Result<u64> {
+        let before = Timestamp::from(before);
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query(r#"DELETE FROM refresh_tokens WHERE user_id IN (SELECT id FROM users WHERE is_deleted = TRUE AND deleted_at < ?)"#).bind(before).execute(&mut *tx).await?;
+        sqlx::query(r#"DELETE FROM user_roles WHERE user_id IN (SELECT id FROM users WHERE is_deleted = TRUE AND deleted_at < ?)"#).bind(before).execute(&mut *tx).await?;
+        let result = sqlx::query(r#"DELETE FROM users WHERE is_deleted = TRUE AND deleted_at < ?"#).bind(before).execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Changes the type of a live user.
+    async fn set_user_type(&self, id: Uuid, user_type: UserType) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET user_type = ?, updated_at = ?, version = version + 1
+            WHERE id = ? AND is_deleted = 0
+            "#,
+        )
+        .bind(user_type)
+        .bind(Timestamp::now())
+        .bind(id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn is_bootstrapped(&self) -> Result<bool> {
+        let row = sqlx::query(r#"SELECT 1 FROM bootstrap LIMIT 1"#).fetch_optional(&mut *self.conn().await?).await?;
+        Ok(row.is_some())
+    }
+
+    /// Inserts the single row of the 'bootstrap' table, its primary key makes a second insert do nothing.
+    async fn mark_bootstrapped(&self) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO bootstrap (id, bootstrapped_at) VALUES (1, ?)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+        )
+        .bind(Timestamp::now())
+        .execute(&mut *self.conn().await?)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[derive(FromRow)]
+struct SearchRow {
+    #[sqlx(flatten)]
+    user: UserDetail,
+    score: f64,
+    snippet: String,
 }
@@ -3,7 +3,37 @@ pub enum Error {
     #[error(transparent)]
     Common(#[from] shared::error::CommonError),
     #[error("Sqlx Error: {0}")]
-    SqlxError(#[from] sqlx::Error),
+    SqlxError(sqlx::Error),
     #[error("Sqlx Migration Error: {0}")]
     SqlxMigrationError(#[from] sqlx::migrate::MigrateError),
+    #[error("Unknown migration version {0}")]
+    UnknownMigration(i64),
+    #[error("Migration {0} has no down migration and cannot be reverted")]
+    IrreversibleMigration(i64),
+    /// A unique index rejected the write, `field` is the column it guards.
+    #[error("`{field}` is already taken")]
+    Conflict { field: &'static str },
+    /// A conditional write found another version than the one it expected.
+    #[error("The record was changed, it is no longer at version {expected}")]
+    VersionMismatch { expected: i64 },
+}
+
+/// Unique indexes by a part of their name and the field they guard. Roles are matched by their table,
+/// SQLite names the column instead of the index for plain column indexes.
+const UNIQUE_FIELDS: &[(&str, &str)] = &[("username", "username"), ("email", "email"), ("roles.name", "name"), ("idx_roles_name", "name")];
+
+impl From<sqlx::Error> for Error {
+    fn from(e: sqlx::Error) -> Self {
+        if let sqlx::Error::Database(db) = &e
+            && db.is_unique_violation()
+        {
+            // Postgres names the constraint, SQLite and MySQL end the message with the index or column,
+            // which keeps the duplicated value in MySQL's message out of the match.
+            let index = db.constraint().or_else(|| db.message().rsplit(' ').next()).unwrap_or_default();
+            if let Some((_, field)) = UNIQUE_FIELDS.iter().find(|(pattern, _)| index.contains(pattern)) {
+                return Error::Conflict { field };
+            }
+        }
+        Error::SqlxError(e)
+    }
 }
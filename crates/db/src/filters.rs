@@ -1 +1,139 @@
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+use crate::search;
+use chrono::{DateTime, Utc};
+use shared::models::{
+    Pagination, SortOrder,
+    user::{DeletedUsers, UserFilter, UserSort, UserSortKey, UserType},
+};
+use sqlx::{Database, Encode, QueryBuilder, Type};
+use uuid::Uuid;
 
+/// Escape character of `LIKE` patterns. Unlike `\` it means the same in the string literals of every backend.
+const LIKE_ESCAPE: char = '!';
+
+fn keyword(order: SortOrder) -> &'static str {
+    match order {
+        SortOrder::Asc => "ASC",
+        SortOrder::Desc => "DESC",
+    }
+}
+
+/// `ORDER BY` terms for `sort`. Ties are broken by ID, so pages don't overlap.
+fn user_order_by(sort: UserSort) -> String {
+    let order = keyword(sort.order);
+    match sort.sort {
+        UserSortKey::Id => format!("id {order}"),
+        key => format!("{} {order}, id {order}", key.column()),
+    }
+}
+
+/// Operator comparing `id` with the cursor, so cursor mode walks in the direction of `order`.
+fn cursor_operator(order: SortOrder) -> &'static str {
+    match order {
+        SortOrder::Asc => ">",
+        SortOrder::Desc => "<",
+    }
+}
+
+/// `needle` with the `LIKE` wildcards in it escaped with [`LIKE_ESCAPE`].
+fn escape_like(needle: &str) -> String {
+    let mut escaped = String::with_capacity(needle.len());
+    for c in needle.chars() {
+        if matches!(c, '%' | '_') || c == LIKE_ESCAPE {
+            escaped.push(LIKE_ESCAPE);
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// `LIKE` pattern matching values that contain `needle`.
+fn contains_pattern(needle: &str) -> String {
+    format!("%{}%", escape_like(needle))
+}
+
+/// Appends the `WHERE` clause of `filter` on the `users` table. Values are only ever bound, never spliced into the SQL.
+/// Times are bound as `T`, the type the backend stores them as.
+pub(crate) fn push_user_filter<'a, DB, T>(query: &mut QueryBuilder<'a, DB>, filter: &UserFilter)
+where
+    DB: Database,
+    String: Encode<'a, DB> + Type<DB>,
+    UserType: Encode<'a, DB> + Type<DB>,
+    T: From<DateTime<Utc>> + Encode<'a, DB> + Type<DB> + 'a,
+{
+    query.push(match filter.deleted {
+        DeletedUsers::Exclude => " WHERE is_deleted = FALSE",
+        DeletedUsers::Include => " WHERE 1 = 1",
+        DeletedUsers::Only => " WHERE is_deleted = TRUE",
+    });
+    for (column, needle) in [("username", &filter.username), ("alias", &filter.alias), ("email", &filter.email)] {
+        if let Some(needle) = needle.as_deref().filter(|needle| !needle.is_empty()) {
+            query.push(format_args!(" AND lower({column}) LIKE lower("));
+            query.push_bind(contains_pattern(needle));
+            query.push(format_args!(") ESCAPE '{LIKE_ESCAPE}'"));
+        }
+    }
+    if let Some(user_type) = filter.user_type {
+        query.push(" AND user_type = ").push_bind(user_type);
+    }
+    if let Some(since) = filter.created_since {
+        query.push(" AND created_at >= ").push_bind(T::from(since));
+    }
+    if let Some(before) = filter.created_before {
+        query.push(" AND created_at < ").push_bind(T::from(before));
+    }
+}
+
+/// Appends the cursor condition and the `ORDER BY` clause of a user list, after [`push_user_filter`].
+pub(crate) fn push_user_order<'a, DB>(query: &mut QueryBuilder<'a, DB>, pagination: &Pagination, sort: UserSort)
+where
+    DB: Database,
+    Uuid: Encode<'a, DB> + Type<DB>,
+{
+    if let Some(after) = pagination.cursor() {
+        query.push(format_args!(" AND id {} ", cursor_operator(sort.order))).push_bind(after);
+    }
+    query.push(" ORDER BY ").push(user_order_by(sort));
+}
+
+/// Appends conditions requiring every term in alias, username or email, after [`push_user_filter`].
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+pub(crate) fn push_user_search<'a, DB>(query: &mut QueryBuilder<'a, DB>, terms: &[String])
+where
+    DB: Database,
+    String: Encode<'a, DB> + Type<DB>,
+{
+    for term in terms {
+        let mut columns = query.separated(" OR ");
+        columns.push_unseparated(" AND (");
+        for column in ["alias", "username", "email"] {
+            columns.push(format_args!("lower({column}) LIKE lower("));
+            columns.push_bind_unseparated(contains_pattern(term));
+            columns.push_unseparated(format_args!(") ESCAPE '{LIKE_ESCAPE}'"));
+        }
+        columns.push_unseparated(")");
+    }
+}
+
+/// Appends an `ORDER BY` of the score [`search::rank`] gives, best first, so the database picks the best matches of all.
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+pub(crate) fn push_user_search_order<'a, DB>(query: &mut QueryBuilder<'a, DB>, terms: &[String])
+where
+    DB: Database,
+    String: Encode<'a, DB> + Type<DB>,
+{
+    let mut score = query.separated(" + ");
+    score.push_unseparated(" ORDER BY ");
+    for term in terms {
+        for (column, weight) in search::WEIGHTS {
+            score.push(format_args!("(CASE WHEN lower({column}) = lower("));
+            score.push_bind_unseparated(term.clone());
+            score.push_unseparated(format_args!(") THEN 3 WHEN lower({column}) LIKE lower("));
+            score.push_bind_unseparated(format!("{}%", escape_like(term)));
+            score.push_unseparated(format_args!(") ESCAPE '{LIKE_ESCAPE}' THEN 2 WHEN lower({column}) LIKE lower("));
+            score.push_bind_unseparated(contains_pattern(term));
+            score.push_unseparated(format_args!(") ESCAPE '{LIKE_ESCAPE}' THEN 1 ELSE 0 END) * {weight}"));
+        }
+    }
+    score.push_unseparated(" DESC, username");
+}
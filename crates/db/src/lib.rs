@@ -1,5 +1,8 @@
 pub mod error;
 pub mod filters;
+pub mod migrate;
+mod conn;
+mod search;
 pub mod db;
 
 pub use error::Error;
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use sqlx::{
+    Database, Pool,
+    migrate::{Migrate, MigrateError, Migration, Migrator},
+};
+
+use crate::{Error, Result};
+
+/// A known migration and whether the database has it applied.
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+    /// Whether a down migration exists, so `migrate down` can revert it.
+    pub reversible: bool,
+}
+
+/// A migration that was, or with `dry_run` would be, applied or reverted.
+#[derive(Debug, Clone)]
+pub struct MigrationStep {
+    pub version: i64,
+    pub description: String,
+    /// The SQL run for this step, the up or the down migration.
+    pub sql: String,
+}
+
+impl From<&Migration> for MigrationStep {
+    fn from(migration: &Migration) -> Self {
+        Self {
+            version: migration.version,
+            description: migration.description.to_string(),
+            sql: migration.sql.to_string(),
+        }
+    }
+}
+
+fn up_migrations(migrator: &Migrator) -> impl Iterator<Item = &Migration> {
+    migrator.iter().filter(|migration| migration.migration_type.is_up_migration())
+}
+
+fn check_version(migrator: &Migrator, version: Option<i64>) -> Result<()> {
+    match version {
+        Some(version) if version != 0 && !up_migrations(migrator).any(|migration| migration.version == version) => Err(Error::UnknownMigration(version)),
+        _ => Ok(()),
+    }
+}
+
+/// Applied versions and their checksums, rejecting a dirty database and applied migrations that were changed or removed since.
+async fn applied<C: Migrate + ?Sized>(migrator: &Migrator, conn: &mut C) -> Result<HashMap<i64, Vec<u8>>> {
+    conn.ensure_migrations_table().await?;
+    if let Some(version) = conn.dirty_version().await? {
+        return Err(MigrateError::Dirty(version).into());
+    }
+    let applied: HashMap<i64, Vec<u8>> = conn.list_applied_migrations().await?.into_iter().map(|migration| (migration.version, migration.checksum.into_owned())).collect();
+    for (version, checksum) in &applied {
+        match up_migrations(migrator).find(|migration| migration.version == *version) {
+            Some(migration) if *migration.checksum != **checksum => return Err(MigrateError::VersionMismatch(*version).into()),
+            Some(_) => {}
+            None => return Err(MigrateError::VersionMissing(*version).into()),
+        }
+    }
+    Ok(applied)
+}
+
+pub(crate) async fn status<DB>(migrator: &Migrator, pool: &Pool<DB>) -> Result<Vec<MigrationStatus>>
+where
+    DB: Database,
+    DB::Connection: Migrate,
+{
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied: Vec<i64> = conn.list_applied_migrations().await?.into_iter().map(|migration| migration.version).collect();
+    Ok(up_migrations(migrator)
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            description: migration.description.to_string(),
+            applied: applied.contains(&migration.version),
+            reversible: migrator.iter().any(|down| down.version == migration.version && down.migration_type.is_down_migration()),
+        })
+        .collect())
+}
+
+/// Applies pending migrations, oldest first, up to and including `to`.
+pub(crate) async fn up<DB>(migrator: &Migrator, pool: &Pool<DB>, to: Option<i64>, dry_run: bool) -> Result<Vec<MigrationStep>>
+where
+    DB: Database,
+    DB::Connection: Migrate,
+{
+    check_version(migrator, to)?;
+    let mut conn = pool.acquire().await?;
+    conn.lock().await?;
+    let result = async {
+        let applied = applied(migrator, &mut *conn).await?;
+        let mut steps = Vec::new();
+        for migration in up_migrations(migrator).filter(|migration| !applied.contains_key(&migration.version) && to.is_none_or(|to| migration.version <= to)) {
+            if !dry_run {
+                conn.apply(migration).await?;
+            }
+            steps.push(MigrationStep::from(migration));
+        }
+        Ok(steps)
+    }
+    .await;
+    conn.unlock().await?;
+    result
+}
+
+/// Reverts applied migrations newer than `to`, newest first. Without `to` only the latest one is reverted, `0` reverts all of them.
+pub(crate) async fn down<DB>(migrator: &Migrator, pool: &Pool<DB>, to: Option<i64>, dry_run: bool) -> Result<Vec<MigrationStep>>
+where
+    DB: Database,
+    DB::Connection: Migrate,
+{
+    check_version(migrator, to)?;
+    let mut conn = pool.acquire().await?;
+    conn.lock().await?;
+    let result = async {
+        let applied = applied(migrator, &mut *conn).await?;
+        let mut versions: Vec<i64> = applied.into_keys().collect();
+        versions.sort_unstable_by(|a, b| b.cmp(a));
+        match to {
+            Some(to) => versions.retain(|version| *version > to),
+            None => versions.truncate(1),
+        }
+        // Check every step first, so a missing down migration doesn't leave the schema half reverted.
+        let migrations = versions
+            .iter()
+            .map(|version| migrator.iter().find(|migration| migration.version == *version && migration.migration_type.is_down_migration()).ok_or(Error::IrreversibleMigration(*version)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut steps = Vec::new();
+        for migration in migrations {
+            if !dry_run {
+                conn.revert(migration).await?;
+            }
+            steps.push(MigrationStep::from(migration));
+        }
+        Ok(steps)
+    }
+    .await;
+    conn.unlock().await?;
+    result
+}
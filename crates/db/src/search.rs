@@ -0,0 +1,93 @@
+use shared::models::user::{UserDetail, UserSearchHit};
+
+/// Markers around matches in snippets.
+pub(crate) const MARK_START: &str = "<mark>";
+pub(crate) const MARK_END: &str = "</mark>";
+
+/// Words shorter than this are dropped from a query, matching the trigram index of SQLite.
+const MIN_TERM_CHARS: usize = 3;
+
+/// Searched columns and their weights, like those of the SQLite index.
+pub(crate) const WEIGHTS: [(&str, u32); 3] = [("username", 3), ("alias", 2), ("email", 1)];
+
+/// Lower case words of `query` long enough to be searched for. Falls back to the whole query if none is.
+pub(crate) fn terms(query: &str) -> Vec<String> {
+    let terms: Vec<String> = query.split_whitespace().filter(|term| term.chars().count() >= MIN_TERM_CHARS).map(str::to_lowercase).collect();
+    if terms.is_empty() { vec![query.trim().to_lowercase()] } else { terms }
+}
+
+/// FTS5 query requiring every term, each quoted so FTS5 operators in it are plain text.
+pub(crate) fn fts5_query(terms: &[String]) -> String {
+    terms.iter().map(|term| format!("\"{}\"", term.replace('"', "\"\""))).collect::<Vec<_>>().join(" ")
+}
+
+/// Ranks users by how well they match every term, for backends without a full-text index. Users missing a term are dropped.
+///
+/// A term scores 3 matching a whole field, 2 its start and 1 anywhere, times the [`WEIGHTS`] of the field.
+pub(crate) fn rank(candidates: Vec<UserDetail>, terms: &[String], limit: usize) -> Vec<UserSearchHit<UserDetail>> {
+    let mut hits: Vec<UserSearchHit<UserDetail>> = candidates
+        .into_iter()
+        .filter_map(|user| {
+            let fields = [user.username.as_str(), user.alias.as_str(), user.email.as_str()];
+            let mut field_scores = [0.0; 3];
+            for term in terms {
+                let scores: [f64; 3] = std::array::from_fn(|i| f64::from(WEIGHTS[i].1) * quality(fields[i], term));
+                if scores.iter().all(|score| *score == 0.0) {
+                    return None;
+                }
+                field_scores.iter_mut().zip(scores).for_each(|(total, score)| *total += score);
+            }
+            let best = (0..fields.len()).max_by(|a, b| field_scores[*a].total_cmp(&field_scores[*b]))?;
+            let snippet = highlight(fields[best], terms);
+            Some(UserSearchHit {
+                score: field_scores.iter().sum(),
+                snippet,
+                user,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.user.username.cmp(&b.user.username)));
+    hits.truncate(limit);
+    hits
+}
+
+fn quality(text: &str, term: &str) -> f64 {
+    match match_len(text, term) {
+        Some(len) if len == text.len() => 3.0,
+        Some(_) => 2.0,
+        None if text.char_indices().any(|(i, _)| match_len(&text[i..], term).is_some()) => 1.0,
+        None => 0.0,
+    }
+}
+
+/// Wraps every occurrence of the terms in `text`, ignoring case, with [`MARK_START`] and [`MARK_END`].
+fn highlight(text: &str, terms: &[String]) -> String {
+    let mut highlighted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(len) = terms.iter().filter_map(|term| match_len(rest, term)).filter(|len| *len > 0).max() {
+            highlighted.push_str(MARK_START);
+            highlighted.push_str(&rest[..len]);
+            highlighted.push_str(MARK_END);
+            rest = &rest[len..];
+        } else {
+            highlighted.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    highlighted
+}
+
+/// Byte length of the start of `text` that is the lower case `term` ignoring case, if it is.
+fn match_len(text: &str, term: &str) -> Option<usize> {
+    let mut expected = term.chars().peekable();
+    for (i, c) in text.char_indices() {
+        if expected.peek().is_none() {
+            return Some(i);
+        }
+        if !c.to_lowercase().all(|lower| expected.next() == Some(lower)) {
+            return None;
+        }
+    }
+    expected.peek().is_none().then_some(text.len())
+}
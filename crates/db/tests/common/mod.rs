@@ -0,0 +1,20 @@
+use shared::models::user::{UserDetailToAddOrUpdate, UserDetailToUpdate};
+
+/// A user to add, the password standing in for a hash.
+pub fn user(username: &str, email: &str) -> UserDetailToAddOrUpdate {
+    UserDetailToAddOrUpdate {
+        alias: format!("Alias of {username}"),
+        username: username.to_owned(),
+        password: "hash".to_owned(),
+        email: email.to_owned(),
+    }
+}
+
+/// The fields of a user to update.
+pub fn update(username: &str, email: &str) -> UserDetailToUpdate {
+    UserDetailToUpdate {
+        alias: format!("Alias of {username}"),
+        username: username.to_owned(),
+        email: email.to_owned(),
+    }
+}
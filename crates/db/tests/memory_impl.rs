@@ -0,0 +1,92 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{update, user};
+use tokio::sync::Barrier;
+use db::{
+    Error,
+    db::{FullDb, UserDb, memory_impl::InMemoryDbImpl},
+};
+use shared::models::{
+    Pagination,
+    user::{UserFilter, UserSort, UserType},
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn concurrent_adds_keep_usernames_unique() {
+    const TASKS: usize = 8;
+    let db = Arc::new(InMemoryDbImpl::new());
+    // Enough users that checking for conflicts takes long enough for the adds to overlap.
+    for i in 0..5000 {
+        db.add_user(UserType::Regular, user(&format!("other{i}"), "")).await.unwrap();
+    }
+    for round in 0..20 {
+        let start = Arc::new(Barrier::new(TASKS));
+        let adds: Vec<_> = (0..TASKS)
+            .map(|i| {
+                let (db, start) = (db.clone(), start.clone());
+                tokio::spawn(async move {
+                    start.wait().await;
+                    db.add_user(UserType::Regular, user(&format!("taken{round}"), &format!("{round}.{i}@example.com"))).await
+                })
+            })
+            .collect();
+
+        let mut added = 0;
+        for add in adds {
+            match add.await.unwrap() {
+                Ok(_) => added += 1,
+                Err(Error::Conflict { field }) => assert_eq!(field, "username"),
+                Err(e) => panic!("unexpected error: {e}"),
+            }
+        }
+        assert_eq!(added, 1, "round {round}");
+    }
+}
+
+#[tokio::test]
+async fn commit_repeats_unique_checks() {
+    let db = InMemoryDbImpl::new();
+    let tx = db.begin().await.unwrap();
+    tx.add_user(UserType::Regular, user("alice", "")).await.unwrap();
+    // Committed meanwhile, the transaction's copy doesn't see it.
+    db.add_user(UserType::Regular, user("bob", "alice@example.com")).await.unwrap();
+    db.add_user(UserType::Regular, user("alice", "")).await.unwrap();
+
+    assert!(matches!(tx.commit().await, Err(Error::Conflict { field: "username" })));
+    let page = db.get_user_list(Pagination::unlimited(), UserSort::default(), UserFilter::default()).await.unwrap();
+    assert_eq!(page.total, 2);
+}
+
+#[tokio::test]
+async fn commit_sees_its_own_renames() {
+    let db = InMemoryDbImpl::new();
+    let alice = db.add_user(UserType::Regular, user("alice", "")).await.unwrap();
+    let bob = db.add_user(UserType::Regular, user("bob", "")).await.unwrap();
+
+    // Swapping two usernames within a transaction passes, as neither old name is left once merged.
+    let tx = db.begin().await.unwrap();
+    tx.update_user(alice, update("carol", ""), None).await.unwrap();
+    tx.update_user(bob, update("alice", ""), None).await.unwrap();
+    tx.update_user(alice, update("bob", ""), None).await.unwrap();
+    tx.commit().await.unwrap();
+
+    assert_eq!(db.get_user_by_username("bob").await.unwrap().map(|user| user.id), Some(alice));
+    assert_eq!(db.get_user_by_username("alice").await.unwrap().map(|user| user.id), Some(bob));
+}
+
+#[tokio::test]
+async fn only_one_transaction_marks_the_bootstrap() {
+    let db = InMemoryDbImpl::new();
+    let (first, second) = (db.begin().await.unwrap(), db.begin().await.unwrap());
+    assert!(first.mark_bootstrapped().await.unwrap());
+    assert!(second.mark_bootstrapped().await.unwrap());
+    assert!(!db.is_bootstrapped().await.unwrap());
+    first.commit().await.unwrap();
+    assert!(db.is_bootstrapped().await.unwrap());
+
+    assert!(matches!(second.commit().await, Err(Error::Conflict { field: "bootstrap" })));
+    assert!(!db.mark_bootstrapped().await.unwrap());
+    assert!(!db.begin().await.unwrap().mark_bootstrapped().await.unwrap());
+}
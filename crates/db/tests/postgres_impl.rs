@@ -0,0 +1,158 @@
+//! Runs against a Postgres server, so the tests are ignored unless asked for:
+//! `POSTGRES_TEST_URL=postgres://postgres@localhost/test cargo test -p db --features postgres --test postgres_impl -- --ignored`.
+//! Each test migrates a schema of its own and leaves it behind, point it at a throwaway database. CI runs them on every push.
+#![cfg(feature = "postgres")]
+
+mod common;
+
+use chrono::{TimeDelta, Utc};
+use common::{update, user};
+use db::{
+    Error,
+    db::{FullDb, MigrateDb, PermissionDb, TokenDb, UserDb, postgres_impl::PostgresDbImpl},
+};
+use shared::models::{
+    permission::{Permission, RoleToAddOrUpdate},
+    user::{UserPatch, UserType},
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// A migrated schema of its own on the server at `POSTGRES_TEST_URL`.
+async fn postgres() -> PostgresDbImpl {
+    let url = std::env::var("POSTGRES_TEST_URL").expect("POSTGRES_TEST_URL must point to a Postgres database the tests may create schemas in");
+    let schema = format!("test_{}", Uuid::now_v7().simple());
+    let pool = PgPool::connect(&url).await.unwrap();
+    sqlx::query(&format!("CREATE SCHEMA {schema}")).execute(&pool).await.unwrap();
+    let separator = if url.contains('?') { '&' } else { '?' };
+    let db = PostgresDbImpl::new(format!("{url}{separator}options=-c%20search_path%3D{schema}")).await.unwrap();
+    db.migrate_up(None, false).await.unwrap();
+    db
+}
+
+fn role(name: &str, permissions: Vec<Permission>) -> RoleToAddOrUpdate {
+    RoleToAddOrUpdate { name: name.to_owned(), description: format!("Description of {name}"), permissions }
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+async fn migrations_revert_and_reapply() {
+    let db = postgres().await;
+    assert!(db.migration_status().await.unwrap().iter().all(|migration| migration.applied));
+
+    db.migrate_down(Some(0), false).await.unwrap();
+    assert!(db.migration_status().await.unwrap().iter().all(|migration| !migration.applied));
+
+    db.migrate_up(None, false).await.unwrap();
+    assert!(db.migration_status().await.unwrap().iter().all(|migration| migration.applied));
+    db.add_user(UserType::Regular, user("alice", "")).await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+async fn users_round_trip() {
+    let db = postgres().await;
+    assert!(!db.exists_user_type(UserType::Admin).await.unwrap());
+    let id = db.add_user(UserType::Admin, user("alice", "alice@example.com")).await.unwrap();
+    assert!(db.exists_user_type(UserType::Admin).await.unwrap());
+
+    let alice = db.get_user(id).await.unwrap().unwrap();
+    assert_eq!((alice.username.as_str(), alice.email.as_str(), alice.user_type, alice.version), ("alice", "alice@example.com", UserType::Admin, 1));
+    assert_eq!(db.get_user_by_username("alice").await.unwrap().map(|user| user.id), Some(id));
+    assert!(matches!(db.add_user(UserType::Regular, user("alice", "")).await, Err(Error::Conflict { field: "username" })));
+    assert!(matches!(db.add_user(UserType::Regular, user("bob", "ALICE@example.com")).await, Err(Error::Conflict { field: "email" })));
+
+    assert!(db.update_user(id, update("alice2", ""), Some(1)).await.unwrap());
+    assert!(matches!(db.update_user(id, update("alice3", ""), Some(1)).await, Err(Error::VersionMismatch { expected: 1 })));
+    assert!(db.patch_user(id, UserPatch { alias: Some("Alice".to_owned()), username: None }).await.unwrap());
+    assert!(db.update_user_password(id, "another-hash").await.unwrap());
+    assert!(db.set_user_type(id, UserType::Regular).await.unwrap());
+    let alice = db.get_user(id).await.unwrap().unwrap();
+    assert_eq!((alice.username.as_str(), alice.alias.as_str(), alice.password.as_str(), alice.user_type), ("alice2", "Alice", "another-hash", UserType::Regular));
+    assert!(alice.version > 2);
+
+    assert!(db.remove_user(id).await.unwrap());
+    assert_eq!(db.get_user(id).await.unwrap(), None);
+    assert!(db.restore_user(id).await.unwrap());
+    assert!(db.remove_user(id).await.unwrap());
+    assert!(db.purge_user(id).await.unwrap());
+    assert!(!db.restore_user(id).await.unwrap());
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+async fn tokens_round_trip() {
+    let db = postgres().await;
+    let user_id = db.add_user(UserType::Regular, user("alice", "")).await.unwrap();
+    let family = Uuid::now_v7();
+    let id = db.add_refresh_token(user_id, family, "first", Utc::now() + TimeDelta::days(1)).await.unwrap();
+    db.add_refresh_token(user_id, family, "second", Utc::now() + TimeDelta::days(1)).await.unwrap();
+
+    let token = db.get_refresh_token("first").await.unwrap().unwrap();
+    assert_eq!((token.id, token.user_id, token.family_id, token.used_at, token.revoked), (id, user_id, family, None, false));
+    assert!(db.use_refresh_token(id).await.unwrap());
+    assert!(!db.use_refresh_token(id).await.unwrap());
+    assert_eq!(db.revoke_refresh_token_family(family).await.unwrap(), 2);
+    assert!(db.get_refresh_token("second").await.unwrap().unwrap().revoked);
+    assert_eq!(db.get_refresh_token("missing").await.unwrap(), None);
+
+    let jti = Uuid::now_v7();
+    assert!(!db.is_access_token_revoked(jti).await.unwrap());
+    db.revoke_access_token(jti, Utc::now() + TimeDelta::hours(1)).await.unwrap();
+    assert!(db.is_access_token_revoked(jti).await.unwrap());
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+async fn roles_round_trip() {
+    let db = postgres().await;
+    let user_id = db.add_user(UserType::Regular, user("alice", "")).await.unwrap();
+    let readers = db.add_role(role("readers", vec![Permission::UserRead, Permission::UserList])).await.unwrap();
+    let writers = db.add_role(role("writers", vec![Permission::UserUpdate, Permission::UserRead])).await.unwrap();
+    assert!(matches!(db.add_role(role("readers", vec![])).await, Err(Error::Conflict { .. })));
+
+    assert!(db.assign_role(user_id, readers).await.unwrap());
+    assert!(db.assign_role(user_id, writers).await.unwrap());
+    assert_eq!(db.get_user_roles(user_id).await.unwrap().len(), 2);
+    assert_eq!(db.get_role_user_ids(readers).await.unwrap(), [user_id]);
+    assert_eq!(db.get_user_permissions(user_id).await.unwrap(), [Permission::UserRead, Permission::UserList, Permission::UserUpdate]);
+
+    assert!(db.update_role(writers, role("editors", vec![Permission::UserCreate])).await.unwrap());
+    let editors = db.get_role(writers).await.unwrap().unwrap();
+    assert_eq!((editors.name.as_str(), editors.permissions.as_slice()), ("editors", [Permission::UserCreate].as_slice()));
+    assert!(db.unassign_role(user_id, readers).await.unwrap());
+    assert!(db.remove_role(writers).await.unwrap());
+    assert!(db.get_user_permissions(user_id).await.unwrap().is_empty());
+    assert_eq!(db.get_role_list().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+async fn rolled_back_writes_are_gone() {
+    let db = postgres().await;
+    let tx = db.begin().await.unwrap();
+    let id = tx.add_user(UserType::Regular, user("alice", "")).await.unwrap();
+    assert!(tx.get_user(id).await.unwrap().is_some());
+    tx.rollback().await.unwrap();
+
+    assert_eq!(db.get_user(id).await.unwrap(), None);
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+async fn search_ranks_every_match() {
+    let db = postgres().await;
+    // More partial matches than a search would ever fetch, all with lower IDs than the best one.
+    let tx = db.begin().await.unwrap();
+    for i in 0..1500 {
+        tx.add_user(UserType::Regular, user(&format!("zebra_fan{i}"), "")).await.unwrap();
+    }
+    tx.commit().await.unwrap();
+    let best = db.add_user(UserType::Regular, user("zebra", "")).await.unwrap();
+
+    let hits = db.search_users("Zebra", 3).await.unwrap();
+    assert_eq!(hits.len(), 3);
+    assert_eq!(hits[0].user.id, best);
+    assert_eq!(hits[0].snippet, "<mark>zebra</mark>");
+    assert!(hits[0].score > hits[1].score);
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use chrono::{TimeDelta, Timelike};
+use common::{update, user};
+use db::db::{FullDb, MigrateDb, UserDb, sqlite_impl::SqliteDbImpl};
+use shared::models::{
+    Pagination,
+    user::{DeletedUsers, UserFilter, UserSort, UserType},
+};
+
+/// A migrated database of its own, gone once the last connection closes.
+async fn sqlite() -> SqliteDbImpl {
+    let db = SqliteDbImpl::new("sqlite::memory:".to_owned()).await.unwrap();
+    db.migrate_up(None, false).await.unwrap();
+    db
+}
+
+async fn count(db: &SqliteDbImpl, filter: UserFilter) -> i64 {
+    db.get_user_list(Pagination::new(1, 100), UserSort::default(), filter).await.unwrap().total
+}
+
+#[tokio::test]
+async fn times_are_stored_to_the_millisecond() {
+    let db = sqlite().await;
+    let id = db.add_user(UserType::Regular, user("alice", "")).await.unwrap();
+    let created_at = db.get_user(id).await.unwrap().unwrap().created_at;
+    assert_eq!(created_at.nanosecond() % 1_000_000, 0);
+
+    let since = |at| UserFilter { created_since: Some(at), ..Default::default() };
+    let before = |at| UserFilter { created_before: Some(at), ..Default::default() };
+    assert_eq!(count(&db, since(created_at)).await, 1);
+    assert_eq!(count(&db, since(created_at + TimeDelta::milliseconds(1))).await, 0);
+    assert_eq!(count(&db, since(created_at.with_nanosecond(0).unwrap())).await, 1);
+    assert_eq!(count(&db, before(created_at)).await, 0);
+    assert_eq!(count(&db, before(created_at + TimeDelta::milliseconds(1))).await, 1);
+}
+
+#[tokio::test]
+async fn purge_cutoff_compares_times_not_text() {
+    let db = sqlite().await;
+    let id = db.add_user(UserType::Regular, user("alice", "")).await.unwrap();
+    db.remove_user(id).await.unwrap();
+    let deleted = UserFilter { deleted: DeletedUsers::Only, ..Default::default() };
+    let deleted_at = db.get_user_list(Pagination::new(1, 100), UserSort::default(), deleted.clone()).await.unwrap().items[0].deleted_at.unwrap();
+
+    assert_eq!(db.purge_users_deleted_before(deleted_at).await.unwrap(), 0);
+    assert_eq!(db.purge_users_deleted_before(deleted_at + TimeDelta::milliseconds(1)).await.unwrap(), 1);
+    assert_eq!(count(&db, deleted).await, 0);
+}
+
+#[tokio::test]
+async fn text_filters_match_literally() {
+    let db = sqlite().await;
+    for username in ["per%cent", "under_score", "bang!name", "o'brien", "plain"] {
+        db.add_user(UserType::Regular, user(username, "")).await.unwrap();
+    }
+    let usernames = async |needle: &str| {
+        let filter = UserFilter { username: Some(needle.to_owned()), ..Default::default() };
+        let page = db.get_user_list(Pagination::new(1, 100), UserSort::default(), filter).await.unwrap();
+        page.items.into_iter().map(|user| user.username).collect::<Vec<_>>()
+    };
+
+    assert_eq!(usernames("%").await, ["per%cent"]);
+    assert_eq!(usernames("_").await, ["under_score"]);
+    assert_eq!(usernames("!").await, ["bang!name"]);
+    assert_eq!(usernames("!n").await, ["bang!name"]);
+    assert_eq!(usernames("r_s").await, ["under_score"]);
+    assert_eq!(usernames("'").await, ["o'brien"]);
+    assert!(usernames("'; DROP TABLE users;--").await.is_empty());
+    assert_eq!(count(&db, UserFilter::default()).await, 5);
+}
+
+#[tokio::test]
+async fn only_one_transaction_marks_the_bootstrap() {
+    let db = sqlite().await;
+    let first = db.begin().await.unwrap();
+    assert!(first.mark_bootstrapped().await.unwrap());
+    // Waits for `first` to release its write lock.
+    let second = tokio::spawn({
+        let second = db.begin().await.unwrap();
+        async move { second.mark_bootstrapped().await.unwrap() }
+    });
+    first.commit().await.unwrap();
+
+    assert!(!second.await.unwrap());
+    assert!(!db.mark_bootstrapped().await.unwrap());
+}
+
+#[tokio::test]
+async fn rolled_back_bootstrap_is_not_marked() {
+    let db = sqlite().await;
+    let tx = db.begin().await.unwrap();
+    assert!(tx.mark_bootstrapped().await.unwrap());
+    tx.rollback().await.unwrap();
+
+    assert!(!db.is_bootstrapped().await.unwrap());
+    assert!(db.mark_bootstrapped().await.unwrap());
+    assert!(db.is_bootstrapped().await.unwrap());
+}
+
+#[tokio::test]
+async fn update_keeps_the_password() {
+    let db = sqlite().await;
+    let id = db.add_user(UserType::Regular, user("alice", "")).await.unwrap();
+    assert!(db.update_user(id, update("alice2", "alice@example.com"), None).await.unwrap());
+
+    let alice = db.get_user(id).await.unwrap().unwrap();
+    assert_eq!((alice.username.as_str(), alice.email.as_str(), alice.password.as_str()), ("alice2", "alice@example.com", "hash"));
+}
@@ -3,9 +3,31 @@ pub enum Error {
     #[error(transparent)]
     Common(#[from] shared::error::CommonError),
     #[error("Storage Error: {0}")]
-    StorageError(#[from] db::Error),
+    StorageError(db::Error),
     #[error("Auth Error: {0}")]
     AuthError(&'static str),
+    #[error("Permission Denied: missing `{0}`")]
+    PermissionDenied(shared::models::permission::Permission),
     #[error("Format Error: {0}")]
     FormatError(&'static str),
+    #[error("Validation Error: {0}")]
+    Validation(crate::validation::ValidationErrors),
+    #[error("Password Hash Error: {0}")]
+    PasswordHashError(argon2::password_hash::Error),
+    /// Another live record already uses the value of `0`, e.g. `username`.
+    #[error("Conflict: `{0}` is already taken")]
+    Conflict(&'static str),
+    /// A conditional write found the record at another version than the caller read.
+    #[error("Precondition Failed: the record was changed, it is no longer at version {0}")]
+    PreconditionFailed(i64),
+}
+
+impl From<db::Error> for Error {
+    fn from(value: db::Error) -> Self {
+        match value {
+            db::Error::Conflict { field } => Error::Conflict(field),
+            db::Error::VersionMismatch { expected } => Error::PreconditionFailed(expected),
+            other => Error::StorageError(other),
+        }
+    }
 }
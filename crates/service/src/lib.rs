@@ -1,24 +1,41 @@
 pub mod error;
+mod password;
 mod preprocess;
 pub mod service_ext;
+pub mod validation;
 use db::db::FullDb;
 pub use error::Error;
-use shared::{config::Config, models::user::UserSummary};
+use password::PasswordHashing;
+use shared::{
+    config::{Config, ConfigHandle},
+    models::{
+        permission::Permission,
+        user::{UserSummary, UserType},
+    },
+};
 use uuid::Uuid;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
 
 #[derive(Clone)]
 pub struct CommonService {
     storage: Arc<dyn FullDb>,
-    config: Arc<Config>,
+    config: ConfigHandle,
+    /// Generated bootstrap token, used when `security.bootstrap_token` is unset. Taken by the bootstrap it succeeds for.
+    bootstrap_token: Arc<Mutex<Option<String>>>,
 }
 
 #[derive(Clone)]
 pub struct CoreService {
     storage: Arc<dyn FullDb>,
+    /// Snapshot of the configuration taken when the service was created.
+    config: Arc<Config>,
+    bootstrap_token: Arc<Mutex<Option<String>>>,
 
     /// Current logined user.
     user: Option<UserSummary>,
@@ -37,18 +54,49 @@ impl Debug for CoreService {
 }
 
 impl CommonService {
-    pub fn new(storage: Arc<dyn FullDb + 'static>, config: Config) -> Self {
-        Self { storage, config: Arc::new(config) }
+    pub fn new(storage: Arc<dyn FullDb + 'static>, config: ConfigHandle) -> Self {
+        Self { storage, config, bootstrap_token: Arc::default() }
+    }
+    /// Accepts `token` once to create the first admin, unless `security.bootstrap_token` is set.
+    pub fn with_bootstrap_token(mut self, token: impl Into<String>) -> Self {
+        self.bootstrap_token = Arc::new(Mutex::new(Some(token.into())));
+        self
     }
     pub fn core(&self, user: Option<UserSummary>) -> CoreService {
-        CoreService { storage: self.storage.clone(), user }
+        CoreService {
+            storage: self.storage.clone(),
+            config: self.config.get(),
+            bootstrap_token: self.bootstrap_token.clone(),
+            user,
+        }
     }
+    /// A service acting as the system itself, holding every permission. For operator commands, never for requests.
+    pub fn system(&self) -> CoreService {
+        self.core(Some(UserSummary {
+            id: Uuid::nil(),
+            user_type: UserType::Admin,
+            alias: "System".to_owned(),
+            username: "system".to_owned(),
+            jti: Uuid::nil(),
+            iat: 0,
+            exp: 0,
+            permissions: Permission::ALL.to_vec(),
+        }))
+    }
+    /// The current configuration, reflecting the latest reload.
     pub fn config(&self) -> Arc<Config> {
-        self.config.clone()
+        self.config.get()
     }
 }
 
 impl CoreService {
+    /// The generated bootstrap token, `None` once a bootstrap succeeded with it.
+    pub(crate) fn generated_bootstrap_token(&self) -> MutexGuard<'_, Option<String>> {
+        self.bootstrap_token.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    pub(crate) fn password_hashing(&self) -> Result<PasswordHashing> {
+        PasswordHashing::new(&self.config.security.password_hash)
+    }
     pub fn try_get_current_user(&self) -> Result<&UserSummary> {
         if let Some(user) = self.user.as_ref() {
             Ok(user)
@@ -56,15 +104,23 @@ impl CoreService {
             Err(crate::Error::AuthError("Please login to continue!"))
         }
     }
-    pub fn only_admin(&self) -> Result<()> {
-        if !self.user.as_ref().is_some_and(|user| user.is_admin()) {
-            return Err(Error::AuthError("Only admin can do!"));
+    /// Requires the current user to hold `permission`.
+    pub fn require(&self, permission: Permission) -> Result<()> {
+        let user = self.try_get_current_user()?;
+        if !user.has_permission(permission) {
+            return Err(Error::PermissionDenied(permission));
         }
         Ok(())
     }
-    pub fn only_admin_or_user(&self, user_id: Uuid) -> Result<()> {
-        if !self.user.as_ref().is_some_and(|user| user.is_admin() || user.id == user_id) {
-            return Err(Error::AuthError("Only admin or user can do!"));
+    /// Requires the current user to hold every permission, as admins do, for actions that grant them.
+    pub fn require_all(&self) -> Result<()> {
+        Permission::ALL.iter().try_for_each(|permission| self.require(*permission))
+    }
+    /// Requires the current user to be `user_id` or to hold `permission`.
+    pub fn require_or_self(&self, permission: Permission, user_id: Uuid) -> Result<()> {
+        let user = self.try_get_current_user()?;
+        if user.id != user_id && !user.has_permission(permission) {
+            return Err(Error::PermissionDenied(permission));
         }
         Ok(())
     }
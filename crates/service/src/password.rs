@@ -0,0 +1,91 @@
+use argon2::{
+    Algorithm, Argon2, Params, Version,
+    password_hash::{self, PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
+};
+use shared::config::PasswordHashConfig;
+use subtle::ConstantTimeEq;
+
+use crate::{Error, Result};
+
+/// Outcome of checking a password against its stored form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    /// The password does not match.
+    Invalid,
+    /// The password matches and the stored hash uses the current parameters.
+    Valid,
+    /// The password matches but the stored form is legacy plaintext or uses outdated parameters.
+    ValidNeedsRehash,
+}
+
+/// Argon2id password hashing configured from `SecurityConfig::password_hash`.
+///
+/// Hashes are stored as PHC strings, so each row carries its own salt and parameters.
+#[derive(Clone)]
+pub struct PasswordHashing {
+    argon2: Argon2<'static>,
+}
+
+impl PasswordHashing {
+    pub fn new(config: &PasswordHashConfig) -> Result<Self> {
+        let params = Params::new(config.memory_kib, config.iterations, config.parallelism, None).map_err(|e| Error::PasswordHashError(e.into()))?;
+        Ok(Self {
+            argon2: Argon2::new(Algorithm::Argon2id, Version::V0x13, params),
+        })
+    }
+
+    /// Hashes `password` into a PHC string with a fresh random salt.
+    pub async fn hash(&self, password: String) -> Result<String> {
+        let this = self.clone();
+        run_blocking(move || this.hash_blocking(&password)).await
+    }
+
+    /// Verifies `password` against `stored` in constant time.
+    ///
+    /// Rows written before hashing was introduced hold plaintext, those still verify but report `ValidNeedsRehash`.
+    pub async fn verify(&self, password: String, stored: String) -> Result<Verification> {
+        let this = self.clone();
+        run_blocking(move || this.verify_blocking(&password, &stored)).await
+    }
+
+    fn hash_blocking(&self, password: &str) -> Result<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = self.argon2.hash_password(password.as_bytes(), &salt).map_err(Error::PasswordHashError)?;
+        Ok(hash.to_string())
+    }
+
+    fn verify_blocking(&self, password: &str, stored: &str) -> Result<Verification> {
+        let Ok(hash) = PasswordHash::new(stored) else {
+            // Legacy plaintext row.
+            let matched: bool = password.as_bytes().ct_eq(stored.as_bytes()).into();
+            return Ok(if matched { Verification::ValidNeedsRehash } else { Verification::Invalid });
+        };
+
+        match self.argon2.verify_password(password.as_bytes(), &hash) {
+            Ok(()) if self.is_current(&hash) => Ok(Verification::Valid),
+            Ok(()) => Ok(Verification::ValidNeedsRehash),
+            Err(password_hash::Error::Password) => Ok(Verification::Invalid),
+            Err(e) => Err(Error::PasswordHashError(e)),
+        }
+    }
+
+    /// Whether `hash` was produced with the currently configured algorithm, version and cost.
+    fn is_current(&self, hash: &PasswordHash<'_>) -> bool {
+        let current = self.argon2.params();
+        hash.algorithm == Algorithm::Argon2id.ident()
+            && hash.version == Some(Version::V0x13.into())
+            && Params::try_from(hash).is_ok_and(|params| {
+                params.m_cost() == current.m_cost() && params.t_cost() == current.t_cost() && params.p_cost() == current.p_cost()
+            })
+    }
+}
+
+async fn run_blocking<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| shared::error::CommonError::InternalError(format!("Password hashing task failed: {e}").into()))?
+}
@@ -1,5 +1,9 @@
+pub mod role;
 pub mod user;
 
+use shared::config::ValidationConfig;
+
 pub trait Preprocess {
-    async fn process(&mut self) -> crate::Result<()>;
+    /// Normalizes the input and checks it against `rules`, reporting every invalid field at once.
+    async fn process(&mut self, rules: &ValidationConfig) -> crate::Result<()>;
 }
@@ -0,0 +1,24 @@
+use serde_json::Value;
+use shared::{config::ValidationConfig, models::permission::RoleToAddOrUpdate};
+
+use crate::{preprocess::Preprocess, validation::ValidationErrors};
+
+impl Preprocess for RoleToAddOrUpdate {
+    async fn process(&mut self, _rules: &ValidationConfig) -> crate::Result<()> {
+        self.name = self.name.trim().to_owned();
+        self.description = self.description.trim().to_owned();
+        self.permissions.sort();
+        self.permissions.dedup();
+
+        let mut errors = ValidationErrors::new();
+        if self.name.is_empty() {
+            errors.add("name", "too_short", "Must not be empty.", [("min", Value::from(1))]);
+        } else if self.name.chars().count() > 64 {
+            errors.add("name", "too_long", "Must be at most 64 characters long.", [("max", Value::from(64))]);
+        }
+        if self.description.chars().count() > 256 {
+            errors.add("description", "too_long", "Must be at most 256 characters long.", [("max", Value::from(256))]);
+        }
+        errors.into_result()
+    }
+}
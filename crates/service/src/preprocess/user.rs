@@ -1,26 +1,69 @@
-use shared::models::user::UserDetailToAddOrUpdate;
+use shared::{
+    config::{FieldRules, ValidationConfig},
+    models::user::{UserDetailToAddOrUpdate, UserDetailToUpdate, UserPatch},
+};
 
-use crate::preprocess::Preprocess;
+use crate::{preprocess::Preprocess, validation::ValidationErrors};
 
 impl Preprocess for UserDetailToAddOrUpdate {
-    async fn process(&mut self) -> crate::Result<()> {
+    async fn process(&mut self, rules: &ValidationConfig) -> crate::Result<()> {
         self.alias = self.alias.trim().to_owned();
         self.username = self.username.trim().to_owned();
         self.password = self.password.trim().to_owned();
+        self.email = self.email.trim().to_owned();
 
-        if self.alias.len() < 3 || self.alias.len() > 64 {
-            return Err(crate::Error::FormatError("Username length only 3 - 64!"));
-        }
-
-        if self.username.len() < 3 || self.username.len() > 64 {
-            return Err(crate::Error::FormatError("Username length only 3 - 64!"));
-        }
+        let mut errors = ValidationErrors::new();
+        errors.check("alias", &self.alias, &rules.alias);
+        errors.check("username", &self.username, &rules.username);
+        errors.check("password", &self.password, &rules.password);
+        errors.check_email("email", &self.email);
+        errors.into_result()
+    }
+}
 
-        if self.password.len() < 8 || self.password.len() > 128 {
-            return Err(crate::Error::FormatError("Password length only 8 - 128!"));
-        }
+impl Preprocess for UserDetailToUpdate {
+    async fn process(&mut self, rules: &ValidationConfig) -> crate::Result<()> {
+        self.alias = self.alias.trim().to_owned();
+        self.username = self.username.trim().to_owned();
         self.email = self.email.trim().to_owned();
 
-        Ok(())
+        let mut errors = ValidationErrors::new();
+        errors.check("alias", &self.alias, &rules.alias);
+        errors.check("username", &self.username, &rules.username);
+        errors.check_email("email", &self.email);
+        errors.into_result()
     }
 }
+
+impl Preprocess for UserPatch {
+    async fn process(&mut self, rules: &ValidationConfig) -> crate::Result<()> {
+        let mut errors = ValidationErrors::new();
+        if let Some(alias) = &mut self.alias {
+            *alias = alias.trim().to_owned();
+            errors.check("alias", alias, &rules.alias);
+        }
+        if let Some(username) = &mut self.username {
+            *username = username.trim().to_owned();
+            errors.check("username", username, &rules.username);
+        }
+        errors.into_result()
+    }
+}
+
+/// Trims a new password and checks it against `rules`.
+pub(crate) fn password(password: &str, rules: &FieldRules) -> crate::Result<String> {
+    let password = password.trim();
+    let mut errors = ValidationErrors::new();
+    errors.check("password", password, rules);
+    errors.into_result()?;
+    Ok(password.to_owned())
+}
+
+/// Trims an email address and checks that it is one, empty removes it.
+pub(crate) fn email(email: &str) -> crate::Result<String> {
+    let email = email.trim();
+    let mut errors = ValidationErrors::new();
+    errors.check_email("email", email);
+    errors.into_result()?;
+    Ok(email.to_owned())
+}
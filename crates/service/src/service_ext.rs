@@ -1 +1,3 @@
+pub mod permission_ext;
+pub mod token_ext;
 pub mod user_ext;
@@ -0,0 +1,107 @@
+use crate::{CoreService, Result, preprocess::Preprocess};
+use async_trait::async_trait;
+use chrono::Utc;
+use db::db::UserDb;
+use shared::models::{
+    permission::{Permission, Role, RoleToAddOrUpdate},
+    user::{UserDetail, UserType},
+};
+use uuid::Uuid;
+
+#[async_trait]
+pub trait PermissionExt {
+    /// Permissions to embed in the access token of `user`. Admins hold every permission.
+    async fn resolve_permissions(&self, user: &UserDetail) -> Result<Vec<Permission>>;
+    async fn add_role(&self, role: RoleToAddOrUpdate) -> Result<Uuid>;
+    async fn update_role(&self, id: Uuid, role: RoleToAddOrUpdate) -> Result<bool>;
+    async fn remove_role(&self, id: Uuid) -> Result<bool>;
+    async fn get_role(&self, id: Uuid) -> Result<Option<Role>>;
+    async fn get_role_list(&self) -> Result<Vec<Role>>;
+    /// Returns `false` if the user or the role doesn't exist, or the role was assigned already.
+    async fn assign_role(&self, user_id: Uuid, role_id: Uuid) -> Result<bool>;
+    async fn unassign_role(&self, user_id: Uuid, role_id: Uuid) -> Result<bool>;
+    async fn get_user_roles(&self, user_id: Uuid) -> Result<Vec<Role>>;
+}
+
+#[async_trait]
+impl PermissionExt for CoreService {
+    async fn resolve_permissions(&self, user: &UserDetail) -> Result<Vec<Permission>> {
+        if user.user_type == UserType::Admin {
+            return Ok(Permission::ALL.to_vec());
+        }
+        Ok(self.storage.get_user_permissions(user.id).await?)
+    }
+    async fn add_role(&self, mut role: RoleToAddOrUpdate) -> Result<Uuid> {
+        self.require(Permission::RoleManage)?;
+        role.process(&self.config.validation).await?;
+        Ok(self.storage.add_role(role).await?)
+    }
+    async fn update_role(&self, id: Uuid, mut role: RoleToAddOrUpdate) -> Result<bool> {
+        self.require(Permission::RoleManage)?;
+        role.process(&self.config.validation).await?;
+        let tx = self.storage.begin().await?;
+        if !tx.update_role(id, role).await? {
+            return Ok(false);
+        }
+        for user_id in tx.get_role_user_ids(id).await? {
+            expire_permissions(&*tx, user_id).await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+    async fn remove_role(&self, id: Uuid) -> Result<bool> {
+        self.require(Permission::RoleManage)?;
+        let tx = self.storage.begin().await?;
+        let user_ids = tx.get_role_user_ids(id).await?;
+        if !tx.remove_role(id).await? {
+            return Ok(false);
+        }
+        for user_id in user_ids {
+            expire_permissions(&*tx, user_id).await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+    async fn get_role(&self, id: Uuid) -> Result<Option<Role>> {
+        self.require(Permission::RoleRead)?;
+        Ok(self.storage.get_role(id).await?)
+    }
+    async fn get_role_list(&self) -> Result<Vec<Role>> {
+        self.require(Permission::RoleRead)?;
+        Ok(self.storage.get_role_list().await?)
+    }
+    async fn assign_role(&self, user_id: Uuid, role_id: Uuid) -> Result<bool> {
+        self.require(Permission::RoleAssign)?;
+        let tx = self.storage.begin().await?;
+        if tx.get_user(user_id).await?.is_none() || tx.get_role(role_id).await?.is_none() {
+            return Ok(false);
+        }
+        let assigned = tx.assign_role(user_id, role_id).await?;
+        if assigned {
+            expire_permissions(&*tx, user_id).await?;
+        }
+        tx.commit().await?;
+        Ok(assigned)
+    }
+    async fn unassign_role(&self, user_id: Uuid, role_id: Uuid) -> Result<bool> {
+        self.require(Permission::RoleAssign)?;
+        let tx = self.storage.begin().await?;
+        let unassigned = tx.unassign_role(user_id, role_id).await?;
+        if unassigned {
+            expire_permissions(&*tx, user_id).await?;
+        }
+        tx.commit().await?;
+        Ok(unassigned)
+    }
+    async fn get_user_roles(&self, user_id: Uuid) -> Result<Vec<Role>> {
+        self.require_or_self(Permission::RoleRead, user_id)?;
+        Ok(self.storage.get_user_roles(user_id).await?)
+    }
+}
+
+/// Permissions are embedded in access tokens, so the user's current tokens are expired
+/// and the next refresh picks up the new set.
+async fn expire_permissions(storage: &(impl UserDb + ?Sized), user_id: Uuid) -> Result<()> {
+    storage.set_user_tokens_valid_after(user_id, Utc::now()).await?;
+    Ok(())
+}
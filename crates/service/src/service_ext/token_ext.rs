@@ -0,0 +1,120 @@
+use crate::{CoreService, Error, Result};
+use async_trait::async_trait;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Duration, Utc};
+use rand::{RngCore, rngs::OsRng};
+use sha2::{Digest, Sha256};
+use shared::models::user::{UserDetail, UserSummary};
+use tracing::warn;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait TokenExt {
+    /// Issues a refresh token that starts a new token family and returns the raw token for the client.
+    async fn issue_refresh_token(&self, user_id: Uuid) -> Result<String>;
+    /// Consumes a refresh token and returns its user together with the next token of the same family.
+    async fn rotate_refresh_token(&self, token: &str) -> Result<(UserDetail, String)>;
+    /// Whether the claims of a correctly signed access token are still accepted.
+    ///
+    /// Rejects revoked tokens, tokens of deleted users, tokens whose user type changed since issue,
+    /// and tokens issued at or before the user's `tokens_valid_after`.
+    async fn is_access_token_valid(&self, claims: &UserSummary) -> Result<bool>;
+    /// Revokes the current access token, and the family of `refresh_token` if it belongs to the current user.
+    async fn logout(&self, refresh_token: Option<&str>) -> Result<()>;
+    /// Revokes every access and refresh token of the current user.
+    async fn logout_everywhere(&self) -> Result<()>;
+}
+
+#[async_trait]
+impl TokenExt for CoreService {
+    async fn issue_refresh_token(&self, user_id: Uuid) -> Result<String> {
+        self.store_refresh_token(user_id, Uuid::now_v7()).await
+    }
+
+    async fn rotate_refresh_token(&self, token: &str) -> Result<(UserDetail, String)> {
+        let Some(stored) = self.storage.get_refresh_token(&hash_token(token)).await? else {
+            return Err(Error::AuthError("Invalid refresh token!"));
+        };
+        if stored.revoked {
+            return Err(Error::AuthError("Refresh token has been revoked!"));
+        }
+        if stored.is_used() {
+            return Err(self.reject_reused(stored.family_id).await);
+        }
+        if stored.is_expired() {
+            return Err(Error::AuthError("Refresh token has expired!"));
+        }
+        if !self.storage.use_refresh_token(stored.id).await? {
+            // Lost a race against another request presenting the same token.
+            return Err(self.reject_reused(stored.family_id).await);
+        }
+
+        let Some(user) = self.storage.get_user(stored.user_id).await? else {
+            self.storage.revoke_refresh_token_family(stored.family_id).await?;
+            return Err(Error::AuthError("User no longer exists!"));
+        };
+        let next = self.store_refresh_token(user.id, stored.family_id).await?;
+        Ok((user, next))
+    }
+
+    async fn is_access_token_valid(&self, claims: &UserSummary) -> Result<bool> {
+        if self.storage.is_access_token_revoked(claims.jti).await? {
+            return Ok(false);
+        }
+        let Some(user) = self.storage.get_user(claims.id).await? else {
+            return Ok(false);
+        };
+        if user.user_type != claims.user_type {
+            return Ok(false);
+        }
+        Ok(user.tokens_valid_after.is_none_or(|valid_after| claims.issued_at() > valid_after))
+    }
+
+    async fn logout(&self, refresh_token: Option<&str>) -> Result<()> {
+        let user = self.try_get_current_user()?;
+        let expires_at = DateTime::<Utc>::from_timestamp(user.exp as i64, 0).unwrap_or_else(Utc::now);
+        self.storage.revoke_access_token(user.jti, expires_at).await?;
+
+        if let Some(refresh_token) = refresh_token
+            && let Some(stored) = self.storage.get_refresh_token(&hash_token(refresh_token)).await?
+            && stored.user_id == user.id
+        {
+            self.storage.revoke_refresh_token_family(stored.family_id).await?;
+        }
+        Ok(())
+    }
+
+    async fn logout_everywhere(&self) -> Result<()> {
+        let user = self.try_get_current_user()?;
+        let tx = self.storage.begin().await?;
+        tx.set_user_tokens_valid_after(user.id, Utc::now()).await?;
+        tx.revoke_user_refresh_tokens(user.id).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+impl CoreService {
+    async fn store_refresh_token(&self, user_id: Uuid, family_id: Uuid) -> Result<String> {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token = URL_SAFE_NO_PAD.encode(bytes);
+
+        let expires_at = Utc::now() + Duration::seconds(self.config.security.refresh_token_ttl_secs as i64);
+        self.storage.add_refresh_token(user_id, family_id, &hash_token(&token), expires_at).await?;
+        Ok(token)
+    }
+
+    /// A used token only comes back if it leaked, so nothing in its family can be trusted anymore.
+    async fn reject_reused(&self, family_id: Uuid) -> Error {
+        warn!("Refresh token reuse detected, revoking token family {family_id}.");
+        if let Err(e) = self.storage.revoke_refresh_token_family(family_id).await {
+            return e.into();
+        }
+        Error::AuthError("Refresh token reuse detected, please login again!")
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
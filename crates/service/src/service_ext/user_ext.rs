@@ -1,50 +1,256 @@
-use crate::{CoreService, Result, preprocess::Preprocess};
+use crate::{
+    CoreService, Error, Result,
+    password::Verification,
+    preprocess::{
+        Preprocess,
+        user::{email, password},
+    },
+    validation::ValidationErrors,
+};
 use async_trait::async_trait;
-use shared::models::{
-    Pagination,
-    user::{UserDetail, UserDetailToAddOrUpdate, UserType},
+use chrono::Utc;
+use subtle::ConstantTimeEq;
+use shared::{
+    config::FieldRules,
+    models::{
+        MAX_PAGE_SIZE, Page, Pagination,
+        permission::Permission,
+        user::{DeletedUsers, UserDetail, UserDetailToAddOrUpdate, UserDetailToUpdate, UserFilter, UserPatch, UserSearchHit, UserSort, UserSortKey, UserType},
+    },
 };
+use tracing::warn;
 use uuid::Uuid;
 
+/// A search query must be long enough for the trigram index to match anything.
+const SEARCH_QUERY_RULES: FieldRules = FieldRules {
+    min_len: 3,
+    max_len: 128,
+    pattern: None,
+};
+const DEFAULT_SEARCH_LIMIT: i64 = 20;
+const MAX_SEARCH_LIMIT: i64 = 100;
+const BOOTSTRAP_CLOSED: Error = Error::AuthError("Bootstrapping is closed, an admin was created already!");
+
 #[async_trait]
 pub trait UserExt {
+    /// Registers a regular user. Anonymous callers may only do so while `security.allow_registration` is enabled.
     async fn add_user(&self, detail: UserDetailToAddOrUpdate) -> Result<Uuid>;
+    /// Creates the first admin for a caller presenting the bootstrap token. Fails once any admin exists or was bootstrapped.
+    async fn bootstrap_admin(&self, token: &str, detail: UserDetailToAddOrUpdate) -> Result<Uuid>;
+    /// Creates a user of the given type on behalf of someone else.
+    async fn create_user(&self, user_type: UserType, detail: UserDetailToAddOrUpdate) -> Result<Uuid>;
     async fn remove_user(&self, id: Uuid) -> Result<bool>;
-    async fn update_user(&self, id: Uuid, detail: UserDetailToAddOrUpdate) -> Result<bool>;
+    /// Makes a deleted user live again. Fails with a conflict if a live user took their username or email meanwhile.
+    async fn restore_user(&self, id: Uuid) -> Result<bool>;
+    /// Permanently deletes a deleted user, which can't be undone.
+    async fn purge_user(&self, id: Uuid) -> Result<bool>;
+    /// Replaces the user's fields but the password. With `version` only if the user is still at that version, failing with `PreconditionFailed` otherwise.
+    async fn update_user(&self, id: Uuid, detail: UserDetailToUpdate, version: Option<i64>) -> Result<bool>;
+    /// Changes only the fields `patch` sets.
+    async fn patch_user(&self, id: Uuid, patch: UserPatch) -> Result<bool>;
+    /// Replaces the password without knowing the current one and signs the user out everywhere. Requires `users.update`,
+    /// even for yourself.
+    async fn set_user_password(&self, id: Uuid, password: &str) -> Result<bool>;
+    /// Like `set_user_password`, but only if `current_password` is the user's password.
+    async fn change_user_password(&self, id: Uuid, current_password: &str, new_password: &str) -> Result<bool>;
+    async fn set_user_email(&self, id: Uuid, email: &str) -> Result<bool>;
+    /// Promotes a user to admin or demotes them to regular. The user's current tokens stop working.
+    async fn set_user_type(&self, id: Uuid, user_type: UserType) -> Result<bool>;
     async fn get_user(&self, id: Uuid) -> Result<Option<UserDetail>>;
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<UserDetail>>;
     async fn get_user_by_validate(&self, username: &str, password: &str) -> Result<Option<UserDetail>>;
-    async fn get_user_list(&self, pagination: Pagination) -> Result<Vec<UserDetail>>;
+    /// Finds users by alias, username or email. Requires `users.list` and `users.read`, as matches in emails are shown.
+    async fn search_users(&self, query: &str, limit: Option<i64>) -> Result<Vec<UserSearchHit<UserDetail>>>;
+    /// Users matching `filter`. Listing deleted users requires `users.delete` too.
+    async fn get_user_list(&self, pagination: Pagination, sort: UserSort, filter: UserFilter) -> Result<Page<UserDetail>>;
 }
 
 #[async_trait]
 impl UserExt for CoreService {
     async fn add_user(&self, mut detail: UserDetailToAddOrUpdate) -> Result<Uuid> {
-        detail.process().await?;
-        let user_type = if self.storage.exists_user_type(UserType::Admin).await? {
-            UserType::Regular
-        } else {
-            UserType::Admin
+        if !self.config.security.allow_registration {
+            self.require(Permission::UserCreate)?;
+        }
+        detail.process(&self.config.validation).await?;
+        detail.password = self.password_hashing()?.hash(detail.password).await?;
+        Ok(self.storage.add_user(UserType::Regular, detail).await?)
+    }
+    async fn bootstrap_admin(&self, token: &str, mut detail: UserDetailToAddOrUpdate) -> Result<Uuid> {
+        let generated = self.config.security.bootstrap_token.is_none();
+        let expected = if generated { self.generated_bootstrap_token().clone() } else { self.config.security.bootstrap_token.clone() };
+        let Some(expected) = expected else {
+            // No token is generated when an admin existed at startup, and the generated one is used up by the first bootstrap.
+            return Err(BOOTSTRAP_CLOSED);
         };
-        Ok(self.storage.add_user(user_type, detail).await?)
+        if !bool::from(token.as_bytes().ct_eq(expected.as_bytes())) {
+            return Err(Error::AuthError("Invalid bootstrap token!"));
+        }
+        if self.storage.is_bootstrapped().await? || self.storage.exists_user_type(UserType::Admin).await? {
+            return Err(BOOTSTRAP_CLOSED);
+        }
+        detail.process(&self.config.validation).await?;
+        detail.password = self.password_hashing()?.hash(detail.password).await?;
+        // Only one transaction can mark the bootstrap, whichever process it runs in.
+        let tx = self.storage.begin().await?;
+        if !tx.mark_bootstrapped().await? || tx.exists_user_type(UserType::Admin).await? {
+            return Err(BOOTSTRAP_CLOSED);
+        }
+        let id = tx.add_user(UserType::Admin, detail).await?;
+        match tx.commit().await {
+            Ok(()) => {
+                if generated {
+                    self.generated_bootstrap_token().take();
+                }
+                Ok(id)
+            }
+            Err(db::Error::Conflict { field: "bootstrap" }) => Err(BOOTSTRAP_CLOSED),
+            Err(e) => Err(e.into()),
+        }
+    }
+    async fn create_user(&self, user_type: UserType, mut detail: UserDetailToAddOrUpdate) -> Result<Uuid> {
+        self.require(Permission::UserCreate)?;
+        if user_type == UserType::Admin {
+            self.require_all()?;
+        }
+        detail.process(&self.config.validation).await?;
+        detail.password = self.password_hashing()?.hash(detail.password).await?;
+        if user_type != UserType::Admin {
+            return Ok(self.storage.add_user(user_type, detail).await?);
+        }
+        // Once there was an admin, bootstrapping stays closed even if every admin is deleted.
+        let tx = self.storage.begin().await?;
+        let id = tx.add_user(user_type, detail).await?;
+        tx.mark_bootstrapped().await?;
+        tx.commit().await?;
+        Ok(id)
     }
     async fn remove_user(&self, id: Uuid) -> Result<bool> {
-        self.only_admin()?;
+        self.require(Permission::UserDelete)?;
         Ok(self.storage.remove_user(id).await?)
     }
-    async fn update_user(&self, id: Uuid, mut detail: UserDetailToAddOrUpdate) -> Result<bool> {
-        self.only_admin_or_user(id)?;
-        detail.process().await?;
-        Ok(self.storage.update_user(id, detail).await?)
+    async fn restore_user(&self, id: Uuid) -> Result<bool> {
+        self.require(Permission::UserDelete)?;
+        Ok(self.storage.restore_user(id).await?)
+    }
+    async fn purge_user(&self, id: Uuid) -> Result<bool> {
+        self.require(Permission::UserDelete)?;
+        Ok(self.storage.purge_user(id).await?)
+    }
+    async fn update_user(&self, id: Uuid, mut detail: UserDetailToUpdate, version: Option<i64>) -> Result<bool> {
+        self.require_or_self(Permission::UserUpdate, id)?;
+        detail.process(&self.config.validation).await?;
+        Ok(self.storage.update_user(id, detail, version).await?)
+    }
+    async fn patch_user(&self, id: Uuid, mut patch: UserPatch) -> Result<bool> {
+        self.require_or_self(Permission::UserUpdate, id)?;
+        patch.process(&self.config.validation).await?;
+        Ok(self.storage.patch_user(id, patch).await?)
+    }
+    async fn set_user_password(&self, id: Uuid, new_password: &str) -> Result<bool> {
+        self.require(Permission::UserUpdate)?;
+        self.replace_password(id, new_password).await
+    }
+    async fn change_user_password(&self, id: Uuid, current_password: &str, new_password: &str) -> Result<bool> {
+        self.require_or_self(Permission::UserUpdate, id)?;
+        let Some(user) = self.storage.get_user(id).await? else {
+            return Ok(false);
+        };
+        if let Verification::Invalid = self.password_hashing()?.verify(current_password.to_owned(), user.password).await? {
+            let mut errors = ValidationErrors::new();
+            errors.add("current_password", "incorrect", "Does not match the current password.", []);
+            return Err(Error::Validation(errors));
+        }
+        self.replace_password(id, new_password).await
+    }
+    async fn set_user_email(&self, id: Uuid, new_email: &str) -> Result<bool> {
+        self.require_or_self(Permission::UserUpdate, id)?;
+        Ok(self.storage.update_user_email(id, &email(new_email)?).await?)
+    }
+    async fn set_user_type(&self, id: Uuid, user_type: UserType) -> Result<bool> {
+        self.require_all()?;
+        // Tokens carry the user type, so `is_access_token_valid` rejects the old ones.
+        if user_type != UserType::Admin {
+            return Ok(self.storage.set_user_type(id, user_type).await?);
+        }
+        let tx = self.storage.begin().await?;
+        if !tx.set_user_type(id, user_type).await? {
+            return Ok(false);
+        }
+        tx.mark_bootstrapped().await?;
+        tx.commit().await?;
+        Ok(true)
     }
     async fn get_user(&self, id: Uuid) -> Result<Option<UserDetail>> {
-        self.only_admin_or_user(id)?;
+        self.require_or_self(Permission::UserRead, id)?;
         Ok(self.storage.get_user(id).await?)
     }
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<UserDetail>> {
+        self.require(Permission::UserRead)?;
+        Ok(self.storage.get_user_by_username(username).await?)
+    }
     async fn get_user_by_validate(&self, username: &str, password: &str) -> Result<Option<UserDetail>> {
-        Ok(self.storage.get_user_by_validate(username, password).await?)
+        let hashing = self.password_hashing()?;
+        let Some(user) = self.storage.get_user_by_username(username).await? else {
+            // Spend the same time as a real verification so unknown usernames can't be told apart.
+            hashing.hash(password.to_owned()).await?;
+            return Ok(None);
+        };
+
+        match hashing.verify(password.to_owned(), user.password.clone()).await? {
+            Verification::Invalid => Ok(None),
+            Verification::Valid => Ok(Some(user)),
+            Verification::ValidNeedsRehash => {
+                let hash = hashing.hash(password.to_owned()).await?;
+                // Same password, so the user's `version` and `updated_at` stay as they are.
+                if let Err(e) = self.storage.rehash_user_password(user.id, &user.password, &hash).await {
+                    warn!("Failed to upgrade password hash of user {}: {e}", user.id);
+                }
+                Ok(Some(user))
+            }
+        }
+    }
+    async fn search_users(&self, query: &str, limit: Option<i64>) -> Result<Vec<UserSearchHit<UserDetail>>> {
+        self.require(Permission::UserList)?;
+        self.require(Permission::UserRead)?;
+        let query = query.trim();
+        let mut errors = ValidationErrors::new();
+        errors.check("q", query, &SEARCH_QUERY_RULES);
+        if let Some(limit) = limit
+            && !(1..=MAX_SEARCH_LIMIT).contains(&limit)
+        {
+            errors.add("limit", "out_of_range", format!("Must be between 1 and {MAX_SEARCH_LIMIT}."), [("min", 1.into()), ("max", MAX_SEARCH_LIMIT.into())]);
+        }
+        errors.into_result()?;
+        Ok(self.storage.search_users(query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT)).await?)
+    }
+    async fn get_user_list(&self, pagination: Pagination, sort: UserSort, filter: UserFilter) -> Result<Page<UserDetail>> {
+        self.require(Permission::UserList)?;
+        if filter.deleted != DeletedUsers::Exclude {
+            self.require(Permission::UserDelete)?;
+        }
+        let mut errors = ValidationErrors::new();
+        if !(1..=MAX_PAGE_SIZE).contains(&pagination.size()) {
+            errors.add("size", "out_of_range", format!("Must be between 1 and {MAX_PAGE_SIZE}."), [("min", 1.into()), ("max", MAX_PAGE_SIZE.into())]);
+        }
+        if pagination.cursor().is_some() && sort.sort != UserSortKey::Id {
+            errors.add("after", "unsupported_sort", "Cursor pagination requires sorting by `id`.", [("sort", "id".into())]);
+        }
+        errors.into_result()?;
+        Ok(self.storage.get_user_list(pagination, sort, filter).await?)
     }
-    async fn get_user_list(&self, pagination: Pagination) -> Result<Vec<UserDetail>> {
-        self.only_admin()?;
-        Ok(self.storage.get_user_list(pagination).await?)
+}
+
+impl CoreService {
+    /// Replaces the password and signs the user out everywhere, once the caller was checked.
+    async fn replace_password(&self, id: Uuid, new_password: &str) -> Result<bool> {
+        let hash = self.password_hashing()?.hash(password(new_password, &self.config.validation.password)?).await?;
+        let tx = self.storage.begin().await?;
+        if !tx.update_user_password(id, &hash).await? {
+            return Ok(false);
+        }
+        tx.set_user_tokens_valid_after(id, Utc::now()).await?;
+        tx.revoke_user_refresh_tokens(id).await?;
+        tx.commit().await?;
+        Ok(true)
     }
 }
@@ -0,0 +1,99 @@
+use std::fmt;
+
+use regex::Regex;
+use serde_json::{Map, Value};
+use shared::config::FieldRules;
+
+/// A single rejected field of a request.
+#[derive(Debug, Clone)]
+pub struct FieldError {
+    pub field: &'static str,
+    /// Machine-readable reason, e.g. `too_short`.
+    pub code: &'static str,
+    pub message: String,
+    /// Values the message refers to, e.g. `min` for `too_short`.
+    pub params: Map<String, Value>,
+}
+
+/// Every rejected field of a request, collected so the caller can fix them all at once.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationErrors(Vec<FieldError>);
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, field: &'static str, code: &'static str, message: impl Into<String>, params: impl IntoIterator<Item = (&'static str, Value)>) {
+        self.0.push(FieldError {
+            field,
+            code,
+            message: message.into(),
+            params: params.into_iter().map(|(name, value)| (name.to_owned(), value)).collect(),
+        });
+    }
+
+    /// Checks `value` against the length and pattern of `rules`.
+    pub fn check(&mut self, field: &'static str, value: &str, rules: &FieldRules) {
+        let len = value.chars().count();
+        if len < rules.min_len {
+            self.add(field, "too_short", format!("Must be at least {} characters long.", rules.min_len), [("min", rules.min_len.into())]);
+        } else if len > rules.max_len {
+            self.add(field, "too_long", format!("Must be at most {} characters long.", rules.max_len), [("max", rules.max_len.into())]);
+        }
+        // `config check` rejects invalid patterns, one slipping through a reload is skipped rather than failing every request.
+        if let Some(pattern) = &rules.pattern
+            && let Ok(regex) = Regex::new(pattern)
+            && !regex.is_match(value)
+        {
+            self.add(field, "invalid_characters", "Contains characters that are not allowed.", [("pattern", pattern.as_str().into())]);
+        }
+    }
+
+    /// Checks that a non-empty `value` is an email address.
+    pub fn check_email(&mut self, field: &'static str, value: &str) {
+        if !value.is_empty() && !is_email(value) {
+            self.add(field, "invalid_email", "Must be an email address such as `name@example.com`.", []);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FieldError> {
+        self.0.iter()
+    }
+
+    /// `Err` with the collected errors, if there are any.
+    pub fn into_result(self) -> crate::Result<()> {
+        if self.is_empty() { Ok(()) } else { Err(crate::Error::Validation(self)) }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "`{}`: {}", error.field, error.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// A pragmatic subset of RFC 5321: a dot-atom local part and a domain of at least two DNS labels.
+fn is_email(value: &str) -> bool {
+    let Some((local, domain)) = value.rsplit_once('@') else {
+        return false;
+    };
+    let local_ok = !local.is_empty()
+        && local.len() <= 64
+        && local.split('.').all(|atom| !atom.is_empty() && atom.chars().all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+/=?^_`{|}~-".contains(c)));
+    let labels: Vec<&str> = domain.split('.').collect();
+    let domain_ok = domain.len() <= 253
+        && labels.len() >= 2
+        && labels.iter().all(|label| !label.is_empty() && label.len() <= 63 && !label.starts_with('-') && !label.ends_with('-') && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+    local_ok && domain_ok
+}
@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use chrono::{TimeDelta, Utc};
+use db::db::{MigrateDb, PermissionDb, TokenDb, UserDb, sqlite_impl::SqliteDbImpl};
+use service::{CommonService, service_ext::user_ext::UserExt};
+use shared::{
+    config::{Config, ConfigHandle, PasswordHashConfig},
+    models::{
+        permission::RoleToAddOrUpdate,
+        user::{UserDetail, UserDetailToAddOrUpdate, UserType},
+    },
+};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+const BOOTSTRAP_TOKEN: &str = "a-bootstrap-token-of-at-least-32-bytes";
+
+/// An in-memory SQLite database, with a second pool on it to plant failures the service can't see coming.
+struct Fixture {
+    storage: Arc<SqliteDbImpl>,
+    service: CommonService,
+    raw: SqlitePool,
+}
+
+impl Fixture {
+    async fn new() -> Self {
+        let url = format!("sqlite:file:{}?mode=memory&cache=shared", Uuid::now_v7());
+        let storage = Arc::new(SqliteDbImpl::new(url.clone()).await.unwrap());
+        storage.migrate_up(None, false).await.unwrap();
+        let raw = SqlitePool::connect(&url).await.unwrap();
+        let mut config = Config::default();
+        config.security.bootstrap_token = Some(BOOTSTRAP_TOKEN.to_owned());
+        // The cheapest hashing allowed, the tests don't need it to be slow.
+        config.security.password_hash = PasswordHashConfig { memory_kib: 8, iterations: 1, parallelism: 1 };
+        let service = CommonService::new(storage.clone(), ConfigHandle::new(config));
+        Self { storage, service, raw }
+    }
+
+    /// Makes every statement causing `event` fail, e.g. `UPDATE OF password ON users`.
+    async fn fail_on(&self, event: &str) {
+        sqlx::query(&format!("CREATE TRIGGER injected_failure BEFORE {event} BEGIN SELECT RAISE(ABORT, 'injected failure'); END"))
+            .execute(&self.raw)
+            .await
+            .unwrap();
+    }
+
+    async fn heal(&self) {
+        sqlx::query("DROP TRIGGER injected_failure").execute(&self.raw).await.unwrap();
+    }
+
+    async fn user(&self, id: Uuid) -> Option<UserDetail> {
+        self.storage.get_user(id).await.unwrap()
+    }
+
+    /// A user with a refresh token and a role, the records password changes and purges touch as well.
+    async fn user_with_token_and_role(&self) -> Uuid {
+        let id = self.service.system().create_user(UserType::Regular, detail("alice")).await.unwrap();
+        self.storage.add_refresh_token(id, Uuid::now_v7(), "token-hash", Utc::now() + TimeDelta::days(1)).await.unwrap();
+        let role = self.storage.add_role(RoleToAddOrUpdate { name: "role".to_owned(), description: String::new(), permissions: vec![] }).await.unwrap();
+        self.storage.assign_role(id, role).await.unwrap();
+        id
+    }
+
+    /// Whether the refresh token and the role of [`user_with_token_and_role`](Self::user_with_token_and_role) are untouched.
+    async fn token_and_role_kept(&self, id: Uuid) -> bool {
+        let token = self.storage.get_refresh_token("token-hash").await.unwrap();
+        token.is_some_and(|token| !token.revoked) && self.storage.get_user_roles(id).await.unwrap().len() == 1
+    }
+}
+
+fn detail(username: &str) -> UserDetailToAddOrUpdate {
+    UserDetailToAddOrUpdate {
+        alias: format!("Alias of {username}"),
+        username: username.to_owned(),
+        password: "password1234".to_owned(),
+        email: String::new(),
+    }
+}
+
+#[tokio::test]
+async fn set_user_password_changes_nothing_if_a_step_fails() {
+    for failing_step in ["UPDATE OF tokens_valid_after ON users", "UPDATE OF revoked ON refresh_tokens"] {
+        let fixture = Fixture::new().await;
+        let id = fixture.user_with_token_and_role().await;
+        let before = fixture.user(id).await;
+        fixture.fail_on(failing_step).await;
+
+        assert!(fixture.service.system().set_user_password(id, "another-password").await.is_err(), "{failing_step}");
+
+        assert_eq!(fixture.user(id).await, before, "{failing_step}");
+        assert!(fixture.token_and_role_kept(id).await, "{failing_step}");
+    }
+}
+
+#[tokio::test]
+async fn purge_user_changes_nothing_if_a_step_fails() {
+    for failing_step in ["DELETE ON refresh_tokens", "DELETE ON user_roles"] {
+        let fixture = Fixture::new().await;
+        let id = fixture.user_with_token_and_role().await;
+        let system = fixture.service.system();
+        system.remove_user(id).await.unwrap();
+        fixture.fail_on(failing_step).await;
+
+        assert!(system.purge_user(id).await.is_err(), "{failing_step}");
+
+        assert!(fixture.token_and_role_kept(id).await, "{failing_step}");
+        assert!(system.restore_user(id).await.unwrap(), "{failing_step}");
+    }
+}
+
+#[tokio::test]
+async fn retention_purge_changes_nothing_if_a_step_fails() {
+    for failing_step in ["DELETE ON user_roles", "DELETE ON users"] {
+        let fixture = Fixture::new().await;
+        let id = fixture.user_with_token_and_role().await;
+        fixture.service.system().remove_user(id).await.unwrap();
+        fixture.fail_on(failing_step).await;
+
+        assert!(fixture.storage.purge_users_deleted_before(Utc::now() + TimeDelta::days(1)).await.is_err(), "{failing_step}");
+
+        assert!(fixture.token_and_role_kept(id).await, "{failing_step}");
+        assert!(fixture.service.system().restore_user(id).await.unwrap(), "{failing_step}");
+    }
+}
+
+#[tokio::test]
+async fn restore_user_changes_nothing_if_it_fails() {
+    let fixture = Fixture::new().await;
+    let id = fixture.user_with_token_and_role().await;
+    let system = fixture.service.system();
+    system.remove_user(id).await.unwrap();
+    fixture.fail_on("UPDATE OF is_deleted ON users").await;
+
+    assert!(system.restore_user(id).await.is_err());
+
+    assert_eq!(fixture.user(id).await, None);
+    fixture.heal().await;
+    assert!(system.restore_user(id).await.unwrap());
+}
+
+#[tokio::test]
+async fn bootstrap_marks_nothing_if_adding_the_admin_fails() {
+    let fixture = Fixture::new().await;
+    fixture.fail_on("INSERT ON users").await;
+
+    assert!(fixture.service.core(None).bootstrap_admin(BOOTSTRAP_TOKEN, detail("root")).await.is_err());
+
+    fixture.heal().await;
+    assert!(fixture.service.core(None).bootstrap_admin(BOOTSTRAP_TOKEN, detail("root")).await.is_ok());
+}
+
+#[tokio::test]
+async fn new_admins_are_not_added_if_marking_the_bootstrap_fails() {
+    let fixture = Fixture::new().await;
+    let id = fixture.user_with_token_and_role().await;
+    let system = fixture.service.system();
+    fixture.fail_on("INSERT ON bootstrap").await;
+
+    assert!(system.create_user(UserType::Admin, detail("root")).await.is_err());
+    assert!(system.set_user_type(id, UserType::Admin).await.is_err());
+
+    assert!(!fixture.storage.exists_user_type(UserType::Admin).await.unwrap());
+    assert_eq!(fixture.storage.get_user_by_username("root").await.unwrap(), None);
+    assert_eq!(fixture.user(id).await.map(|user| user.user_type), Some(UserType::Regular));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_bootstraps_create_one_admin() {
+    let fixture = Fixture::new().await;
+    let bootstraps: Vec<_> = (0..4)
+        .map(|i| {
+            let service = fixture.service.clone();
+            tokio::spawn(async move { service.core(None).bootstrap_admin(BOOTSTRAP_TOKEN, detail(&format!("root{i}"))).await })
+        })
+        .collect();
+
+    let mut created = 0;
+    for bootstrap in bootstraps {
+        if bootstrap.await.unwrap().is_ok() {
+            created += 1;
+        }
+    }
+    assert_eq!(created, 1);
+}
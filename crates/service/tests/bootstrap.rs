@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use db::db::{MigrateDb, UserDb, sqlite_impl::SqliteDbImpl};
+use service::{CommonService, Error, service_ext::user_ext::UserExt};
+use shared::{
+    config::{Config, ConfigHandle, PasswordHashConfig},
+    models::user::{UserDetailToAddOrUpdate, UserType},
+};
+use uuid::Uuid;
+
+const TOKEN: &str = "a-bootstrap-token-of-at-least-32-bytes";
+
+/// A service on a migrated in-memory SQLite database, accepting `TOKEN` from the config or as the generated token.
+async fn setup(configured: bool) -> (Arc<SqliteDbImpl>, CommonService) {
+    let storage = Arc::new(SqliteDbImpl::new("sqlite::memory:".to_owned()).await.unwrap());
+    storage.migrate_up(None, false).await.unwrap();
+    let mut config = Config::default();
+    // The cheapest hashing allowed, the tests don't need it to be slow.
+    config.security.password_hash = PasswordHashConfig { memory_kib: 8, iterations: 1, parallelism: 1 };
+    if configured {
+        config.security.bootstrap_token = Some(TOKEN.to_owned());
+    }
+    let service = CommonService::new(storage.clone(), ConfigHandle::new(config));
+    let service = if configured { service } else { service.with_bootstrap_token(TOKEN) };
+    (storage, service)
+}
+
+fn detail(username: &str) -> UserDetailToAddOrUpdate {
+    UserDetailToAddOrUpdate {
+        alias: format!("Alias of {username}"),
+        username: username.to_owned(),
+        password: "password1234".to_owned(),
+        email: String::new(),
+    }
+}
+
+async fn delete_for_good(service: &CommonService, id: Uuid) {
+    assert!(service.system().remove_user(id).await.unwrap());
+    assert!(service.system().purge_user(id).await.unwrap());
+}
+
+#[tokio::test]
+async fn bootstrap_stays_closed_once_the_admin_is_deleted() {
+    let (storage, service) = setup(true).await;
+    let id = service.core(None).bootstrap_admin(TOKEN, detail("root")).await.unwrap();
+    delete_for_good(&service, id).await;
+
+    assert!(!storage.exists_user_type(UserType::Admin).await.unwrap());
+    assert!(storage.is_bootstrapped().await.unwrap());
+    assert!(service.core(None).bootstrap_admin(TOKEN, detail("root")).await.is_err());
+}
+
+#[tokio::test]
+async fn generated_token_is_used_up_by_the_bootstrap() {
+    let (_, service) = setup(false).await;
+    assert!(matches!(service.core(None).bootstrap_admin("wrong", detail("root")).await, Err(Error::AuthError("Invalid bootstrap token!"))));
+    service.core(None).bootstrap_admin(TOKEN, detail("root")).await.unwrap();
+
+    // Without a token left, even a wrong one isn't compared anymore.
+    let refused = service.core(None).bootstrap_admin("wrong", detail("root2")).await;
+    assert!(matches!(refused, Err(Error::AuthError(message)) if message != "Invalid bootstrap token!"));
+    assert!(service.core(None).bootstrap_admin(TOKEN, detail("root2")).await.is_err());
+}
+
+#[tokio::test]
+async fn any_admin_closes_bootstrap() {
+    let (storage, service) = setup(true).await;
+    let created = service.system().create_user(UserType::Admin, detail("created")).await.unwrap();
+    delete_for_good(&service, created).await;
+    assert!(storage.is_bootstrapped().await.unwrap());
+
+    let (storage, service) = setup(true).await;
+    let promoted = service.system().create_user(UserType::Regular, detail("promoted")).await.unwrap();
+    assert!(!storage.is_bootstrapped().await.unwrap());
+    service.system().set_user_type(promoted, UserType::Admin).await.unwrap();
+    delete_for_good(&service, promoted).await;
+    assert!(service.core(None).bootstrap_admin(TOKEN, detail("root")).await.is_err());
+}
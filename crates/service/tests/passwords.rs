@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use db::db::{MigrateDb, sqlite_impl::SqliteDbImpl};
+use service::{CommonService, CoreService, Error, service_ext::user_ext::UserExt};
+use shared::{
+    config::{Config, ConfigHandle, PasswordHashConfig},
+    models::{
+        permission::Permission,
+        user::{UserDetailToAddOrUpdate, UserDetailToUpdate, UserSummary, UserType},
+    },
+};
+use uuid::Uuid;
+
+const PASSWORD: &str = "password1234";
+
+/// A service on a migrated in-memory SQLite database.
+async fn setup() -> CommonService {
+    let storage = Arc::new(SqliteDbImpl::new("sqlite::memory:".to_owned()).await.unwrap());
+    storage.migrate_up(None, false).await.unwrap();
+    let mut config = Config::default();
+    // The cheapest hashing allowed, the tests don't need it to be slow.
+    config.security.password_hash = PasswordHashConfig { memory_kib: 8, iterations: 1, parallelism: 1 };
+    CommonService::new(storage, ConfigHandle::new(config))
+}
+
+/// Adds a regular user without any role and acts as them.
+async fn signed_in(service: &CommonService, username: &str) -> (Uuid, CoreService) {
+    let detail = UserDetailToAddOrUpdate { alias: format!("Alias of {username}"), username: username.to_owned(), password: PASSWORD.to_owned(), email: String::new() };
+    let id = service.system().create_user(UserType::Regular, detail).await.unwrap();
+    let user = UserSummary {
+        id,
+        user_type: UserType::Regular,
+        alias: format!("Alias of {username}"),
+        username: username.to_owned(),
+        jti: Uuid::now_v7(),
+        iat: 0,
+        exp: 0,
+        permissions: vec![],
+    };
+    (id, service.core(Some(user)))
+}
+
+#[tokio::test]
+async fn updating_a_user_keeps_the_password() {
+    let service = setup().await;
+    let (id, alice) = signed_in(&service, "alice").await;
+    let before = alice.get_user(id).await.unwrap().unwrap();
+
+    let detail = UserDetailToUpdate { alias: "Alice".to_owned(), username: "alice2".to_owned(), email: "alice@example.com".to_owned() };
+    assert!(alice.update_user(id, detail, None).await.unwrap());
+
+    let after = alice.get_user(id).await.unwrap().unwrap();
+    assert_eq!((after.username.as_str(), after.password.as_str()), ("alice2", before.password.as_str()));
+    assert!(alice.get_user_by_validate("alice2", PASSWORD).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn own_password_changes_only_with_the_current_one() {
+    let service = setup().await;
+    let (id, alice) = signed_in(&service, "alice").await;
+
+    assert!(matches!(alice.set_user_password(id, "another-password").await, Err(Error::PermissionDenied(Permission::UserUpdate))));
+    assert!(matches!(alice.change_user_password(id, "wrong-password", "another-password").await, Err(Error::Validation(_))));
+    assert!(alice.get_user_by_validate("alice", PASSWORD).await.unwrap().is_some());
+
+    assert!(alice.change_user_password(id, PASSWORD, "another-password").await.unwrap());
+    assert!(alice.get_user_by_validate("alice", "another-password").await.unwrap().is_some());
+    assert!(service.system().set_user_password(id, "third-password").await.unwrap());
+    assert!(alice.get_user_by_validate("alice", "third-password").await.unwrap().is_some());
+}
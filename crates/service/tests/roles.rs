@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use db::db::{MigrateDb, sqlite_impl::SqliteDbImpl};
+use service::{
+    CommonService,
+    service_ext::{permission_ext::PermissionExt, user_ext::UserExt},
+};
+use shared::{
+    config::{Config, ConfigHandle, PasswordHashConfig},
+    models::{
+        permission::RoleToAddOrUpdate,
+        user::{UserDetailToAddOrUpdate, UserType},
+    },
+};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn assigning_to_a_missing_user_or_role_assigns_nothing() {
+    let storage = Arc::new(SqliteDbImpl::new("sqlite::memory:".to_owned()).await.unwrap());
+    storage.migrate_up(None, false).await.unwrap();
+    let mut config = Config::default();
+    // The cheapest hashing allowed, the test doesn't need it to be slow.
+    config.security.password_hash = PasswordHashConfig { memory_kib: 8, iterations: 1, parallelism: 1 };
+    let system = CommonService::new(storage, ConfigHandle::new(config)).system();
+    let detail = UserDetailToAddOrUpdate { alias: "Alice".to_owned(), username: "alice".to_owned(), password: "password1234".to_owned(), email: String::new() };
+    let user = system.create_user(UserType::Regular, detail).await.unwrap();
+    let role = system.add_role(RoleToAddOrUpdate { name: "readers".to_owned(), description: String::new(), permissions: vec![] }).await.unwrap();
+
+    assert!(!system.assign_role(Uuid::now_v7(), role).await.unwrap());
+    assert!(!system.assign_role(user, Uuid::now_v7()).await.unwrap());
+    assert!(system.get_user_roles(user).await.unwrap().is_empty());
+
+    assert!(system.assign_role(user, role).await.unwrap());
+    assert!(!system.assign_role(user, role).await.unwrap());
+}
@@ -1,10 +1,18 @@
 use default_functions::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::default::Default;
+use std::path::PathBuf;
 
 mod default_functions;
+mod handle;
+mod loader;
+mod validate;
 
-#[derive(Debug, Deserialize, Clone)]
+pub use handle::ConfigHandle;
+pub use loader::{ConfigChange, ConfigEntry, ConfigLoader, ConfigProblem, ConfigSource, ENV_PREFIX, ENV_SEPARATOR, LoadedConfig, RUNTIME_SAFE_KEYS, Severity};
+pub use validate::KNOWN_DB_SCHEMES;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(default)]
 pub struct Config {
     #[serde(default)]
@@ -15,9 +23,18 @@ pub struct Config {
 
     #[serde(default)]
     pub db: DbConfig,
+
+    #[serde(default)]
+    pub log: LogConfig,
+
+    #[serde(default)]
+    pub validation: ValidationConfig,
+
+    #[serde(default)]
+    pub retention: RetentionConfig,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct ServerConfig {
     #[serde(default = "default_server_host")]
@@ -25,37 +42,137 @@ pub struct ServerConfig {
 
     #[serde(default = "default_server_port")]
     pub port: u16,
+
+    /// Serve an interactive API reference at `/api/docs`. The spec at `/api/openapi.json` is always served.
+    #[serde(default)]
+    pub openapi_ui: bool,
+
+    /// Origins allowed to call the API from a browser, e.g. `https://app.example.com`. `*` allows any origin.
+    #[serde(default = "default_server_cors_origins")]
+    pub cors_origins: Vec<String>,
+
+    /// Development mode. Startup checks such as the auth key strength only warn instead of aborting.
+    /// Never enable it in production.
+    #[serde(default)]
+    pub dev_mode: bool,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct SecurityConfig {
+    /// Secret used to sign access tokens, at least 32 bytes long.
     #[serde(default = "default_security_auth_key")]
     pub auth_key: String,
+
+    /// File holding the auth key, e.g. a mounted container secret. Replaces `auth_key` when set.
+    #[serde(default)]
+    pub auth_key_file: Option<PathBuf>,
+
+    #[serde(default)]
+    pub password_hash: PasswordHashConfig,
+
+    /// Lifetime of the JWT access token issued at login, in seconds.
+    #[serde(default = "default_security_access_token_ttl_secs")]
+    pub access_token_ttl_secs: u64,
+
+    /// Lifetime of a refresh token, in seconds. Each rotation issues a fresh token with a full lifetime.
+    #[serde(default = "default_security_refresh_token_ttl_secs")]
+    pub refresh_token_ttl_secs: u64,
+
+    /// Let anyone register a regular account with `POST /api/users`. When disabled only holders of `users.create` add users.
+    #[serde(default = "default_security_allow_registration")]
+    pub allow_registration: bool,
+
+    /// Secret to present to `POST /api/bootstrap` to create the first admin, at least 32 bytes long.
+    /// When unset, a one-time token is generated and logged at startup while no admin exists. Either stops working once
+    /// there was an admin, even if it is deleted later. Changes require a restart.
+    #[serde(default)]
+    pub bootstrap_token: Option<String>,
+}
+
+/// Argon2id cost parameters used when hashing passwords.
+///
+/// Changing these does not invalidate existing hashes, stored hashes with other parameters
+/// are upgraded transparently on the next successful login.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PasswordHashConfig {
+    /// Memory cost in KiB.
+    #[serde(default = "default_password_hash_memory_kib")]
+    pub memory_kib: u32,
+
+    /// Number of iterations.
+    #[serde(default = "default_password_hash_iterations")]
+    pub iterations: u32,
+
+    /// Degree of parallelism.
+    #[serde(default = "default_password_hash_parallelism")]
+    pub parallelism: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LogConfig {
+    /// Log filter in `tracing` directive syntax, e.g. `info` or `info,sqlx=warn`.
+    #[serde(default = "default_log_level")]
+    pub level: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Rules for user input, checked whenever a user is created or updated.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ValidationConfig {
+    #[serde(default = "default_validation_username")]
+    pub username: FieldRules,
+
+    #[serde(default = "default_validation_alias")]
+    pub alias: FieldRules,
+
+    #[serde(default = "default_validation_password")]
+    pub password: FieldRules,
+}
+
+/// Rules for a single text field. Lengths count characters, after trimming surrounding whitespace.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FieldRules {
+    pub min_len: usize,
+
+    pub max_len: usize,
+
+    /// Regular expression the whole value must match, e.g. `^[a-z0-9_]+$`.
+    #[serde(default)]
+    pub pattern: Option<String>,
+}
+
+/// How long deleted data is kept before the retention worker purges it for good.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// Days a deleted user can still be restored, 0 (the default) keeps deleted users forever.
+    #[serde(default = "default_retention_deleted_user_days")]
+    pub deleted_user_days: u32,
+
+    /// Seconds between two purges.
+    #[serde(default = "default_retention_interval_secs")]
+    pub interval_secs: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct DbConfig {
+    /// Storage URL, the scheme picks the backend: `sqlite:`, `postgres:`, `mysql:` or `memory:`.
     #[serde(default = "default_db_url")]
     pub url: String,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Config {
-            server: ServerConfig::default(),
-            security: SecurityConfig::default(),
-            db: DbConfig::default(),
-        }
-    }
-}
-
 impl Default for ServerConfig {
     fn default() -> Self {
         ServerConfig {
             host: default_server_host(),
             port: default_server_port(),
+            openapi_ui: false,
+            cors_origins: default_server_cors_origins(),
+            dev_mode: false,
         }
     }
 }
@@ -64,6 +181,47 @@ impl Default for SecurityConfig {
     fn default() -> Self {
         SecurityConfig {
             auth_key: default_security_auth_key(),
+            auth_key_file: None,
+            password_hash: PasswordHashConfig::default(),
+            access_token_ttl_secs: default_security_access_token_ttl_secs(),
+            refresh_token_ttl_secs: default_security_refresh_token_ttl_secs(),
+            allow_registration: default_security_allow_registration(),
+            bootstrap_token: None,
+        }
+    }
+}
+
+impl Default for PasswordHashConfig {
+    fn default() -> Self {
+        PasswordHashConfig {
+            memory_kib: default_password_hash_memory_kib(),
+            iterations: default_password_hash_iterations(),
+            parallelism: default_password_hash_parallelism(),
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig { level: default_log_level() }
+    }
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        RetentionConfig {
+            deleted_user_days: default_retention_deleted_user_days(),
+            interval_secs: default_retention_interval_secs(),
+        }
+    }
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        ValidationConfig {
+            username: default_validation_username(),
+            alias: default_validation_alias(),
+            password: default_validation_password(),
         }
     }
 }
@@ -74,6 +232,26 @@ impl Default for DbConfig {
     }
 }
 
+/// Minimum length of `auth_key` in bytes, the size of the HS256 output.
+pub const MIN_AUTH_KEY_LEN: usize = 32;
+
+impl SecurityConfig {
+    /// Rejects the built-in default key and keys shorter than [`MIN_AUTH_KEY_LEN`].
+    pub fn check_auth_key(&self) -> Result<(), crate::error::CommonError> {
+        let message = if self.auth_key == default_security_auth_key() {
+            "the built-in default key is public, anyone can sign valid tokens with it"
+        } else if self.auth_key.len() < MIN_AUTH_KEY_LEN {
+            "the key is shorter than 32 bytes"
+        } else {
+            return Ok(());
+        };
+        Err(crate::error::CommonError::InvalidConfig {
+            origin: "security.auth_key".to_string(),
+            message: message.into(),
+        })
+    }
+}
+
 impl Config {
     pub fn load_from_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self, crate::error::CommonError> {
         toml::from_str(&std::fs::read_to_string(path)?).map_err(|e| crate::error::CommonError::InvalidInput { message: e.to_string().into() })
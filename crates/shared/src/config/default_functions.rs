@@ -1,3 +1,5 @@
+use super::FieldRules;
+
 pub fn default_server_host() -> String {
     "0.0.0.0".to_string()
 }
@@ -6,10 +8,74 @@ pub fn default_server_port() -> u16 {
     8888
 }
 
+pub fn default_server_cors_origins() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
 pub fn default_security_auth_key() -> String {
     "unsafe-default-auth-key".to_string()
 }
 
+pub fn default_security_access_token_ttl_secs() -> u64 {
+    60 * 60
+}
+
+pub fn default_security_refresh_token_ttl_secs() -> u64 {
+    30 * 24 * 60 * 60
+}
+
+pub fn default_security_allow_registration() -> bool {
+    true
+}
+
+pub fn default_password_hash_memory_kib() -> u32 {
+    19 * 1024
+}
+
+pub fn default_password_hash_iterations() -> u32 {
+    2
+}
+
+pub fn default_password_hash_parallelism() -> u32 {
+    1
+}
+
+pub fn default_log_level() -> String {
+    "info".to_string()
+}
+
+pub fn default_retention_deleted_user_days() -> u32 {
+    0
+}
+
+pub fn default_retention_interval_secs() -> u64 {
+    60 * 60
+}
+
+pub fn default_validation_username() -> FieldRules {
+    FieldRules {
+        min_len: 3,
+        max_len: 64,
+        pattern: None,
+    }
+}
+
+pub fn default_validation_alias() -> FieldRules {
+    FieldRules {
+        min_len: 3,
+        max_len: 64,
+        pattern: None,
+    }
+}
+
+pub fn default_validation_password() -> FieldRules {
+    FieldRules {
+        min_len: 8,
+        max_len: 128,
+        pattern: None,
+    }
+}
+
 pub fn default_db_url() -> String {
     "sqlite:./data.sqlite".to_string() 
-}
\ No newline at end of file
+}
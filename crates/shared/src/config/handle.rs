@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+
+use crate::config::Config;
+
+/// The current configuration, shared by every service and swapped as a whole on reload.
+///
+/// Readers take a snapshot with [`ConfigHandle::get`] and keep using it, so a request never sees
+/// half of an old and half of a new configuration.
+#[derive(Debug, Clone)]
+pub struct ConfigHandle(Arc<ArcSwap<Config>>);
+
+impl ConfigHandle {
+    pub fn new(config: Config) -> Self {
+        Self(Arc::new(ArcSwap::from_pointee(config)))
+    }
+
+    pub fn get(&self) -> Arc<Config> {
+        self.0.load_full()
+    }
+
+    pub fn set(&self, config: Config) {
+        self.0.store(Arc::new(config));
+    }
+}
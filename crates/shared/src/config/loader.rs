@@ -0,0 +1,530 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use toml::{Table, Value, de::DeTable};
+
+use crate::{config::Config, error::CommonError};
+
+/// Prefix of environment variables that override configuration values, e.g. `APP__DB__URL`.
+pub const ENV_PREFIX: &str = "APP";
+/// Separates the prefix, sections and key of an environment variable name.
+pub const ENV_SEPARATOR: &str = "__";
+/// Values never printed in a configuration report.
+const SECRET_KEYS: &[&str] = &["security.auth_key", "security.bootstrap_token"];
+/// Keys, or sections by their prefix, that a reload applies to the running server. Everything else needs a restart.
+pub const RUNTIME_SAFE_KEYS: &[&str] = &["log.level", "server.cors_origins", "security.access_token_ttl_secs", "security.refresh_token_ttl_secs", "security.password_hash", "security.allow_registration", "validation", "retention"];
+
+/// Where an effective configuration value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    File(PathBuf),
+    Env(String),
+    Cli,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => f.write_str("default"),
+            ConfigSource::File(path) => write!(f, "file {}", path.display()),
+            ConfigSource::Env(name) => write!(f, "env {name}"),
+            ConfigSource::Cli => f.write_str("command line"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// A configuration value that is unknown, mistyped or invalid.
+#[derive(Debug, Clone)]
+pub struct ConfigProblem {
+    pub severity: Severity,
+    /// Dotted path of the value, such as `server.port`.
+    pub key: String,
+    pub message: String,
+    pub source: ConfigSource,
+    /// Line and column, both 1-based, when the value comes from the configuration file.
+    pub position: Option<(usize, usize)>,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: `{}` {} ({}", self.severity, self.key, self.message, self.source)?;
+        if let Some((line, column)) = self.position {
+            write!(f, ":{line}:{column}")?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// Builds a [`Config`] from layers, each overriding the previous one:
+/// defaults, the TOML file, `APP__SECTION__KEY` environment variables and `section.key=value` command line overrides.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLoader {
+    file: Option<PathBuf>,
+    /// Whether loading fails if `file` doesn't exist, rather than skipping it.
+    file_required: bool,
+    overrides: Vec<String>,
+}
+
+/// The effective configuration together with the source of each of its values.
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    /// Values with a problem fall back to their default so the rest can still be checked.
+    pub config: Config,
+    /// The configuration file, if it existed and was read.
+    pub file: Option<PathBuf>,
+    /// Source of every value, keyed by its dotted path such as `db.url`.
+    pub sources: BTreeMap<String, ConfigSource>,
+    pub problems: Vec<ConfigProblem>,
+    effective: Table,
+}
+
+/// One line of a configuration report.
+#[derive(Debug, Clone)]
+pub struct ConfigEntry<'a> {
+    pub key: &'a str,
+    /// The value as TOML, `"<redacted>"` for secrets.
+    pub value: String,
+    pub source: &'a ConfigSource,
+}
+
+impl fmt::Display for ConfigEntry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {} ({})", self.key, self.value, self.source)
+    }
+}
+
+/// A value that differs between the running and a reloaded configuration.
+#[derive(Debug, Clone)]
+pub struct ConfigChange {
+    pub key: String,
+    /// The values as TOML, `None` if unset. Secrets are redacted.
+    pub old: Option<String>,
+    pub new: Option<String>,
+    /// Whether the change took effect, `false` if it needs a restart.
+    pub applied: bool,
+}
+
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unset = "<unset>".to_string();
+        write!(f, "{}: {} -> {}", self.key, self.old.as_ref().unwrap_or(&unset), self.new.as_ref().unwrap_or(&unset))?;
+        if !self.applied {
+            f.write_str(" (requires restart)")?;
+        }
+        Ok(())
+    }
+}
+
+impl ConfigLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// TOML file to read. Loading fails if it doesn't exist.
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self.file_required = true;
+        self
+    }
+
+    /// TOML file to read if it exists, for a default location nobody asked for explicitly.
+    pub fn default_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self.file_required = false;
+        self
+    }
+
+    /// Command line overrides in the form `section.key=value`.
+    pub fn overrides(mut self, overrides: impl IntoIterator<Item = String>) -> Self {
+        self.overrides.extend(overrides);
+        self
+    }
+
+    /// Loads the configuration with the environment variables of the current process.
+    pub fn load(&self) -> Result<LoadedConfig, CommonError> {
+        self.load_with_env(std::env::vars())
+    }
+
+    /// Fails only if the file is missing, cannot be read or is not valid TOML, every other problem is collected in
+    /// [`LoadedConfig::problems`].
+    pub fn load_with_env(&self, vars: impl IntoIterator<Item = (String, String)>) -> Result<LoadedConfig, CommonError> {
+        let defaults = Table::try_from(Config::default()).map_err(|e| invalid(&ConfigSource::Default, e.to_string()))?;
+        let mut layers = Layers {
+            effective: defaults.clone(),
+            sources: BTreeMap::new(),
+            problems: Vec::new(),
+            file: None,
+        };
+        record_sources(&defaults, "", &ConfigSource::Default, &mut layers.sources);
+
+        let mut file = None;
+        if let Some(path) = self.file.as_ref().filter(|path| self.file_required && !path.is_file()) {
+            return Err(CommonError::NotFound { path: path.clone() });
+        }
+        if let Some(path) = self.file.as_ref().filter(|path| path.is_file()) {
+            let source = ConfigSource::File(path.clone());
+            let text = std::fs::read_to_string(path)?;
+            let table: Table = toml::from_str(&text).map_err(|e| invalid(&source, e.to_string()))?;
+            layers.file = Some((path.clone(), text));
+
+            let mut mismatches = Vec::new();
+            merge(&mut layers.effective, table, "", &source, &mut layers.sources, &mut mismatches);
+            for (key, message) in mismatches {
+                layers.problem(Severity::Error, key, message, Some(source.clone()));
+            }
+            file = Some(path.clone());
+        }
+
+        let env_prefix = format!("{ENV_PREFIX}{ENV_SEPARATOR}");
+        let mut vars: Vec<_> = vars.into_iter().filter(|(name, _)| name.starts_with(&env_prefix)).collect();
+        vars.sort();
+        for (name, raw) in vars {
+            let path: Vec<String> = name[env_prefix.len()..].split(ENV_SEPARATOR).map(str::to_lowercase).collect();
+            layers.set(&path, &raw, ConfigSource::Env(name));
+        }
+
+        for entry in &self.overrides {
+            match entry.split_once('=') {
+                Some((key, raw)) => {
+                    let path: Vec<String> = key.trim().split('.').map(str::to_owned).collect();
+                    layers.set(&path, raw, ConfigSource::Cli);
+                }
+                None => layers.problem(Severity::Error, entry.clone(), "is not in the form `section.key=value`", Some(ConfigSource::Cli)),
+            }
+        }
+
+        let mut config = layers.deserialize(&defaults)?;
+
+        if let Some(path) = config.security.auth_key_file.clone() {
+            match layers.read_secret_file(&path) {
+                Ok(key) => {
+                    config.security.auth_key = key.clone();
+                    if let Some(Value::Table(security)) = layers.effective.get_mut("security") {
+                        security.insert("auth_key".to_string(), Value::String(key));
+                    }
+                    layers.sources.insert("security.auth_key".to_string(), ConfigSource::File(path));
+                }
+                Err(message) => layers.problem(Severity::Error, "security.auth_key_file", message, None),
+            }
+        }
+
+        for (key, severity, message) in config.validate() {
+            layers.problem(severity, key, message, None);
+        }
+
+        Ok(LoadedConfig {
+            config,
+            file,
+            sources: layers.sources,
+            problems: layers.problems,
+            effective: layers.effective,
+        })
+    }
+}
+
+impl LoadedConfig {
+    /// Every effective value with its source, secrets redacted.
+    pub fn entries(&self) -> Vec<ConfigEntry<'_>> {
+        self.sources
+            .iter()
+            .map(|(key, source)| {
+                let value = lookup(&self.effective, key).map(|value| display_value(key, value)).unwrap_or_default();
+                ConfigEntry { key, value, source }
+            })
+            .collect()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.problems.iter().any(|problem| problem.severity == Severity::Error)
+    }
+
+    /// Takes the runtime-safe values of `next` and keeps the current value of everything else.
+    /// Returns the configuration to run with and every value that differs, applied or not.
+    pub fn reload(&self, next: &LoadedConfig) -> Result<(LoadedConfig, Vec<ConfigChange>), CommonError> {
+        let mut effective = self.effective.clone();
+        let mut sources = self.sources.clone();
+        let mut changes = Vec::new();
+
+        let keys: std::collections::BTreeSet<&String> = self.sources.keys().chain(next.sources.keys()).collect();
+        for key in keys {
+            let (old, new) = (lookup(&self.effective, key), lookup(&next.effective, key));
+            if old == new {
+                continue;
+            }
+            let applied = is_runtime_safe(key);
+            if applied {
+                replace(&mut effective, key, new.cloned());
+                match next.sources.get(key) {
+                    Some(source) => sources.insert(key.clone(), source.clone()),
+                    None => sources.remove(key),
+                };
+            }
+            changes.push(ConfigChange {
+                key: key.clone(),
+                old: old.map(|value| display_value(key, value)),
+                new: new.map(|value| display_value(key, value)),
+                applied,
+            });
+        }
+
+        let config = effective.clone().try_into().map_err(|e: toml::de::Error| invalid_message("the reloaded configuration", e.to_string()))?;
+        let reloaded = LoadedConfig {
+            config,
+            file: next.file.clone(),
+            sources,
+            problems: next.problems.clone(),
+            effective,
+        };
+        Ok((reloaded, changes))
+    }
+}
+
+fn is_runtime_safe(key: &str) -> bool {
+    RUNTIME_SAFE_KEYS.iter().any(|safe| key == *safe || key.strip_prefix(safe).is_some_and(|rest| rest.starts_with('.')))
+}
+
+fn display_value(key: &str, value: &Value) -> String {
+    if SECRET_KEYS.contains(&key) { Value::String("<redacted>".to_string()).to_string() } else { value.to_string() }
+}
+
+/// Sets or, with `None`, removes the value at the dotted `key`.
+fn replace(table: &mut Table, key: &str, value: Option<Value>) {
+    let (sections, last) = key.rsplit_once('.').unwrap_or(("", key));
+    let mut current = table;
+    for section in sections.split('.').filter(|section| !section.is_empty()) {
+        current = match current.entry(section.to_string()).or_insert_with(|| Value::Table(Table::new())) {
+            Value::Table(table) => table,
+            _ => return,
+        };
+    }
+    match value {
+        Some(value) => current.insert(last.to_string(), value),
+        None => current.remove(last),
+    };
+}
+
+/// State of a load in progress.
+struct Layers {
+    effective: Table,
+    sources: BTreeMap<String, ConfigSource>,
+    problems: Vec<ConfigProblem>,
+    /// Path and text of the configuration file, used to locate problems in it.
+    file: Option<(PathBuf, String)>,
+}
+
+impl Layers {
+    /// Records a problem. Without an explicit `source` it is attributed to the layer that set `key`.
+    fn problem(&mut self, severity: Severity, key: impl Into<String>, message: impl Into<String>, source: Option<ConfigSource>) {
+        let key = key.into();
+        let source = source.or_else(|| self.sources.get(&key).cloned()).unwrap_or(ConfigSource::Default);
+        let position = match (&source, &self.file) {
+            (ConfigSource::File(path), Some((file, text))) if path == file => locate(text, &key),
+            _ => None,
+        };
+        self.problems.push(ConfigProblem {
+            severity,
+            key,
+            message: message.into(),
+            source,
+            position,
+        });
+    }
+
+    /// Sets the value at `path`, parsed to the type of the value it replaces.
+    fn set(&mut self, path: &[String], raw: &str, source: ConfigSource) {
+        let key = path.join(".");
+        if path.iter().any(|part| part.is_empty()) {
+            return self.problem(Severity::Error, key, "is not a valid key", Some(source));
+        }
+        let (last, sections) = path.split_last().expect("split always yields a part");
+
+        let mut current = &mut self.effective;
+        for section in sections {
+            current = match current.entry(section.clone()).or_insert_with(|| Value::Table(Table::new())) {
+                Value::Table(table) => table,
+                _ => return self.problem(Severity::Error, key, format!("cannot be set, `{section}` is not a section"), Some(source)),
+            };
+        }
+
+        match parse_like(current.get(last), raw) {
+            Ok(value) => {
+                current.insert(last.clone(), value);
+                self.sources.insert(key, source);
+            }
+            Err(message) => self.problem(Severity::Error, key, message, Some(source)),
+        }
+    }
+
+    /// Deserializes the merged layers, reporting unknown keys. A value that fails to deserialize is reported
+    /// and replaced by its default, so every such value is found in one pass.
+    fn deserialize(&mut self, defaults: &Table) -> Result<Config, CommonError> {
+        loop {
+            let mut unknown = Vec::new();
+            let mut on_unknown = |path: serde_ignored::Path| unknown.push(path.to_string());
+            let deserializer = serde_ignored::Deserializer::new(Value::Table(self.effective.clone()), &mut on_unknown);
+            match serde_path_to_error::deserialize::<_, Config>(deserializer) {
+                Ok(config) => {
+                    for key in unknown {
+                        self.problem(Severity::Error, key, "is not a known setting", None);
+                    }
+                    return Ok(config);
+                }
+                Err(e) => {
+                    let key = e.path().to_string();
+                    let message = e.inner().message().to_string();
+                    if !reset(&mut self.effective, defaults, &key) {
+                        return Err(invalid_message(&key, message));
+                    }
+                    self.problem(Severity::Error, key.clone(), message, None);
+                    self.sources.insert(key, ConfigSource::Default);
+                }
+            }
+        }
+    }
+
+    fn read_secret_file(&self, path: &Path) -> Result<String, String> {
+        if self.sources.get("security.auth_key").is_some_and(|source| *source != ConfigSource::Default) {
+            return Err("cannot be combined with `security.auth_key`, set only one of them".to_string());
+        }
+        let key = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        // Secret files usually end with a newline.
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(format!("points to {}, which is empty", path.display()));
+        }
+        Ok(key.to_string())
+    }
+}
+
+fn invalid(source: &ConfigSource, message: impl Into<Cow<'static, str>>) -> CommonError {
+    invalid_message(&source.to_string(), message)
+}
+
+fn invalid_message(origin: &str, message: impl Into<Cow<'static, str>>) -> CommonError {
+    CommonError::InvalidConfig {
+        origin: origin.to_string(),
+        message: message.into(),
+    }
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() { key.to_string() } else { format!("{prefix}.{key}") }
+}
+
+fn record_sources(table: &Table, prefix: &str, source: &ConfigSource, sources: &mut BTreeMap<String, ConfigSource>) {
+    for (key, value) in table {
+        let key = join(prefix, key);
+        match value {
+            Value::Table(table) => record_sources(table, &key, source, sources),
+            _ => {
+                sources.insert(key, source.clone());
+            }
+        }
+    }
+}
+
+/// Merges `layer` into `base`, skipping values whose type differs from the value they replace.
+fn merge(base: &mut Table, layer: Table, prefix: &str, source: &ConfigSource, sources: &mut BTreeMap<String, ConfigSource>, mismatches: &mut Vec<(String, String)>) {
+    for (key, value) in layer {
+        let path = join(prefix, &key);
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(layer)) => merge(base, layer, &path, source, sources, mismatches),
+            (Some(existing), value) if existing.type_str() != value.type_str() => {
+                mismatches.push((path, format!("expected {}, got {}", existing.type_str(), value.type_str())));
+            }
+            (_, value) => {
+                match &value {
+                    Value::Table(table) => record_sources(table, &path, source, sources),
+                    _ => {
+                        sources.insert(path, source.clone());
+                    }
+                }
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn lookup<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+    let mut parts = key.split('.');
+    let mut value = table.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_table()?.get(part)?;
+    }
+    Some(value)
+}
+
+/// Restores the default of `key`, or removes it if it has none. Returns `false` if there was nothing to reset.
+fn reset(table: &mut Table, defaults: &Table, key: &str) -> bool {
+    let (sections, last) = key.rsplit_once('.').unwrap_or(("", key));
+    let mut current = table;
+    for section in sections.split('.').filter(|section| !section.is_empty()) {
+        current = match current.get_mut(section) {
+            Some(Value::Table(table)) => table,
+            _ => return false,
+        };
+    }
+    match lookup(defaults, key) {
+        Some(default) if current.get(last) != Some(default) => {
+            current.insert(last.to_string(), default.clone());
+            true
+        }
+        Some(_) => false,
+        None => current.remove(last).is_some(),
+    }
+}
+
+/// Line and column of `key` in the TOML `text`.
+fn locate(text: &str, key: &str) -> Option<(usize, usize)> {
+    let document = DeTable::parse(text).ok()?;
+    let mut table = document.get_ref();
+    let mut span = None;
+    for part in key.split('.') {
+        let (name, value) = table.iter().find(|(name, _)| name.get_ref() == part)?;
+        span = Some(name.span());
+        match value.get_ref().as_table() {
+            Some(inner) => table = inner,
+            None => break,
+        }
+    }
+    let offset = span?.start;
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rfind('\n').map_or(offset, |newline| offset - newline - 1) + 1;
+    Some((line, column))
+}
+
+fn parse_like(existing: Option<&Value>, raw: &str) -> Result<Value, String> {
+    let raw = raw.trim();
+    match existing {
+        Some(Value::Integer(_)) => raw.parse().map(Value::Integer).map_err(|_| format!("expected an integer, got `{raw}`")),
+        Some(Value::Float(_)) => raw.parse().map(Value::Float).map_err(|_| format!("expected a number, got `{raw}`")),
+        Some(Value::Boolean(_)) => raw.parse().map(Value::Boolean).map_err(|_| format!("expected `true` or `false`, got `{raw}`")),
+        Some(Value::Array(_)) | Some(Value::Table(_)) => {
+            let mut table: Table = toml::from_str(&format!("value = {raw}")).map_err(|_| format!("expected a TOML array or inline table, got `{raw}`"))?;
+            Ok(table.remove("value").unwrap_or(Value::String(raw.to_string())))
+        }
+        // Strings, and optional values that are unset by default.
+        _ => Ok(Value::String(raw.to_string())),
+    }
+}
@@ -0,0 +1,108 @@
+use std::{borrow::Cow, net::IpAddr};
+
+use crate::config::{Config, Severity};
+
+/// Database URL schemes that have a backend. Whether it is compiled in is checked when connecting.
+pub const KNOWN_DB_SCHEMES: &[&str] = &["sqlite", "memory", "postgres", "postgresql", "mysql", "mariadb"];
+
+/// Longest token lifetime, far below what would overflow the expiry timestamps.
+pub const MAX_TOKEN_TTL_SECS: u64 = 10 * 365 * 24 * 60 * 60;
+
+/// Longest retention of deleted users, far below what would overflow the purge cutoff.
+pub const MAX_RETENTION_DAYS: u32 = 100 * 365;
+
+impl Config {
+    /// Checks values that deserialize fine but cannot work, as `(key, severity, message)`.
+    pub fn validate(&self) -> Vec<(&'static str, Severity, Cow<'static, str>)> {
+        let mut problems = Vec::new();
+
+        let host = self.server.host.as_str();
+        if host.parse::<IpAddr>().is_err() && !is_hostname(host) {
+            problems.push(("server.host", Severity::Error, format!("`{host}` is neither an IP address nor a host name").into()));
+        }
+        if self.server.port == 0 {
+            problems.push(("server.port", Severity::Error, "must be between 1 and 65535".into()));
+        }
+
+        for origin in &self.server.cors_origins {
+            if origin != "*" && !(origin.starts_with("http://") || origin.starts_with("https://")) {
+                problems.push(("server.cors_origins", Severity::Error, format!("`{origin}` is neither `*` nor an origin such as `https://app.example.com`").into()));
+            }
+        }
+
+        match self.db.url.split_once(':') {
+            Some((scheme, _)) if KNOWN_DB_SCHEMES.contains(&scheme) => {}
+            Some((scheme, _)) => problems.push(("db.url", Severity::Error, format!("unknown scheme `{scheme}`, expected one of {}", KNOWN_DB_SCHEMES.join(", ")).into())),
+            None => problems.push(("db.url", Severity::Error, "has no scheme, e.g. `sqlite:./data.sqlite`".into())),
+        }
+
+        if let Err(crate::error::CommonError::InvalidConfig { message, .. }) = self.security.check_auth_key() {
+            // Dev mode exists so a local server runs without generating a key first.
+            let severity = if self.server.dev_mode { Severity::Warning } else { Severity::Error };
+            problems.push(("security.auth_key", severity, message));
+        }
+        if let Some(token) = &self.security.bootstrap_token
+            && token.len() < crate::config::MIN_AUTH_KEY_LEN
+        {
+            let severity = if self.server.dev_mode { Severity::Warning } else { Severity::Error };
+            problems.push(("security.bootstrap_token", severity, "the token is shorter than 32 bytes".into()));
+        }
+        for (key, ttl_secs) in [("security.access_token_ttl_secs", self.security.access_token_ttl_secs), ("security.refresh_token_ttl_secs", self.security.refresh_token_ttl_secs)] {
+            if !(1..=MAX_TOKEN_TTL_SECS).contains(&ttl_secs) {
+                problems.push((key, Severity::Error, format!("must be between 1 and {MAX_TOKEN_TTL_SECS} (10 years)").into()));
+            }
+        }
+        if self.retention.deleted_user_days > MAX_RETENTION_DAYS {
+            problems.push(("retention.deleted_user_days", Severity::Error, format!("must be at most {MAX_RETENTION_DAYS} (100 years), 0 disables the purge").into()));
+        }
+        if self.retention.interval_secs == 0 {
+            problems.push(("retention.interval_secs", Severity::Error, "must be greater than 0".into()));
+        }
+
+        let password_hash = &self.security.password_hash;
+        if password_hash.iterations == 0 {
+            problems.push(("security.password_hash.iterations", Severity::Error, "must be greater than 0".into()));
+        }
+        if password_hash.parallelism == 0 {
+            problems.push(("security.password_hash.parallelism", Severity::Error, "must be greater than 0".into()));
+        }
+        if password_hash.memory_kib < 8 * password_hash.parallelism {
+            problems.push(("security.password_hash.memory_kib", Severity::Error, "must be at least 8 times `parallelism`".into()));
+        }
+
+        let fields = [
+            ("validation.username.min_len", "validation.username.max_len", "validation.username.pattern", &self.validation.username),
+            ("validation.alias.min_len", "validation.alias.max_len", "validation.alias.pattern", &self.validation.alias),
+            ("validation.password.min_len", "validation.password.max_len", "validation.password.pattern", &self.validation.password),
+        ];
+        for (min_len_key, max_len_key, pattern_key, rules) in fields {
+            if rules.max_len == 0 {
+                problems.push((max_len_key, Severity::Error, "must be greater than 0".into()));
+            } else if rules.min_len > rules.max_len {
+                problems.push((min_len_key, Severity::Error, format!("{} is greater than `max_len` {}, no value is allowed", rules.min_len, rules.max_len).into()));
+            }
+            if let Some(pattern) = &rules.pattern
+                && let Err(e) = regex::Regex::new(pattern)
+            {
+                // Syntax errors quote the pattern over several lines, the last one says what is wrong.
+                let e = e.to_string();
+                let reason = e.lines().last().unwrap_or_default().trim_start_matches("error: ");
+                problems.push((pattern_key, Severity::Error, format!("is not a valid regular expression: {reason}").into()));
+            }
+        }
+
+        if self.log.level.trim().is_empty() {
+            problems.push(("log.level", Severity::Error, "must not be empty".into()));
+        }
+
+        problems
+    }
+}
+
+fn is_hostname(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= 253
+        && host
+            .split('.')
+            .all(|label| !label.is_empty() && label.len() <= 63 && !label.starts_with('-') && !label.ends_with('-') && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+}
@@ -18,6 +18,10 @@ pub enum CommonError {
     #[error("Invalid input: {message}")]
     InvalidInput { message: Cow<'static, str> },
 
+    /// A configuration layer holds a value that cannot be used.
+    #[error("Invalid configuration from {origin}: {message}")]
+    InvalidConfig { origin: String, message: Cow<'static, str> },
+
     /// A file was expected but not found at the specified path.
     #[error("File not found: {path}")]
     NotFound { path: PathBuf },
@@ -1,22 +1,65 @@
 use std::cmp;
 
+pub mod permission;
+pub mod token;
 pub mod user;
 
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Most items a single page may ask for.
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct Pagination {
+    /// 1-based page number, ignored in cursor mode.
+    #[serde(default = "first_page")]
     page: i64,
+    /// Number of items per page, at most 100.
     size: i64,
+    /// Switches to cursor mode: only items after this ID are returned. Pass `next_cursor` of the previous page.
+    #[serde(default)]
+    after: Option<Uuid>,
+}
+
+fn first_page() -> i64 {
+    1
 }
 
 impl Pagination {
     pub fn new(page: i64, size: i64) -> Self {
-        Self { page, size }
+        Self { page, size, after: None }
+    }
+
+    /// Cursor mode, the `size` items following `after`.
+    pub fn after(after: Uuid, size: i64) -> Self {
+        Self { page: 1, size, after: Some(after) }
     }
 
     pub fn unlimited() -> Self {
-        Self { page: -1, size: -1 }
+        Self { page: -1, size: -1, after: None }
+    }
+
+    /// Requested number of items per page, before `get_safety`.
+    pub fn size(&self) -> i64 {
+        self.size
+    }
+
+    /// The cursor, if in cursor mode.
+    pub fn cursor(&self) -> Option<Uuid> {
+        self.after
+    }
+
+    /// Rows to skip and the most rows to return, `None` for no limit. Cursor mode never skips rows.
+    pub fn offset_limit(&self) -> (i64, Option<i64>) {
+        if self.is_unlimited() {
+            return (0, None);
+        }
+        let (page, size) = self.get_safety();
+        let offset = if self.after.is_some() { 0 } else { (page - 1).saturating_mul(size) };
+        (offset, Some(size))
     }
 
     pub fn is_unlimited(&self) -> bool {
@@ -28,3 +71,58 @@ impl Pagination {
         (cmp::max(self.page, 1), cmp::max(self.size, 1))
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// One page of a list.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Number of items across all pages.
+    pub total: i64,
+    /// 1-based number of this page, `null` in cursor mode.
+    pub page: Option<i64>,
+    /// Requested number of items per page.
+    pub size: i64,
+    pub has_next: bool,
+    /// Pass as `after` to fetch the next page in cursor mode. Only set if `has_next` and the list is sorted by ID.
+    pub next_cursor: Option<Uuid>,
+}
+
+impl<T> Page<T> {
+    /// Builds a page from the rows fetched with one more than `pagination` asks for, the extra row only tells
+    /// whether there is a next page. `cursor` is the ID of an item if the list is sorted by ID.
+    pub fn new(mut items: Vec<T>, total: i64, pagination: &Pagination, cursor: impl Fn(&T) -> Option<Uuid>) -> Self {
+        let (offset, limit) = pagination.offset_limit();
+        let has_next = limit.is_some_and(|limit| items.len() as i64 > limit);
+        if let Some(limit) = limit {
+            items.truncate(limit as usize);
+        }
+        let page = if pagination.cursor().is_some() { None } else { Some(limit.map_or(1, |limit| offset / limit + 1)) };
+        Self {
+            next_cursor: if has_next { items.last().and_then(cursor) } else { None },
+            total,
+            page,
+            size: limit.unwrap_or(items.len() as i64),
+            has_next,
+            items,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            page: self.page,
+            size: self.size,
+            has_next: self.has_next,
+            next_cursor: self.next_cursor,
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A single action a user may be allowed to perform. Stored as its string form, e.g. `users.read`.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, ToSchema)]
+pub enum Permission {
+    /// Create users on behalf of others.
+    #[serde(rename = "users.create")]
+    UserCreate,
+    /// Read any user, not only yourself.
+    #[serde(rename = "users.read")]
+    UserRead,
+    /// List all users.
+    #[serde(rename = "users.list")]
+    UserList,
+    /// Update any user, not only yourself.
+    #[serde(rename = "users.update")]
+    UserUpdate,
+    /// Delete users.
+    #[serde(rename = "users.delete")]
+    UserDelete,
+    /// Read roles and the roles of any user.
+    #[serde(rename = "roles.read")]
+    RoleRead,
+    /// Create, update and delete roles.
+    #[serde(rename = "roles.manage")]
+    RoleManage,
+    /// Assign roles to and remove roles from users.
+    #[serde(rename = "roles.assign")]
+    RoleAssign,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, FromRow, ToSchema)]
+pub struct Role {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    #[sqlx(skip)]
+    pub permissions: Vec<Permission>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct RoleToAddOrUpdate {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub permissions: Vec<Permission>,
+}
+
+impl Permission {
+    pub const ALL: &'static [Permission] = &[
+        Permission::UserCreate,
+        Permission::UserRead,
+        Permission::UserList,
+        Permission::UserUpdate,
+        Permission::UserDelete,
+        Permission::RoleRead,
+        Permission::RoleManage,
+        Permission::RoleAssign,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::UserCreate => "users.create",
+            Permission::UserRead => "users.read",
+            Permission::UserList => "users.list",
+            Permission::UserUpdate => "users.update",
+            Permission::UserDelete => "users.delete",
+            Permission::RoleRead => "roles.read",
+            Permission::RoleManage => "roles.manage",
+            Permission::RoleAssign => "roles.assign",
+        }
+    }
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Permission {
+    type Err = crate::error::CommonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Permission::ALL
+            .iter()
+            .find(|permission| permission.as_str() == s)
+            .copied()
+            .ok_or_else(|| crate::error::CommonError::InvalidInput {
+                message: format!("Unknown permission '{s}'").into(),
+            })
+    }
+}
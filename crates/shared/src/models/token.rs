@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+/// A server-stored refresh token. Only the SHA-256 hash of the token is kept.
+///
+/// Every token issued by rotation shares the `family_id` of the login it descends from,
+/// so a reused token can revoke the whole chain.
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub revoked: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RefreshToken {
+    pub fn is_used(&self) -> bool {
+        self.used_at.is_some()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
@@ -1,10 +1,13 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::{FromRow, Type};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, Copy, Clone, Type)]
-#[repr(u16)]
+use crate::models::{SortOrder, permission::Permission};
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Type, ToSchema)]
+#[repr(i16)]
 pub enum UserType {
     Admin,
     Regular,
@@ -16,7 +19,15 @@ pub struct UserSummary {
     pub user_type: UserType,
     pub alias: String,
     pub username: String,
+    /// Unique token ID, used to revoke a single token.
+    ///
+    /// A UUIDv7, so it also records when the token was issued with millisecond precision.
+    pub jti: Uuid,
+    pub iat: usize,
     pub exp: usize,
+    /// Permissions resolved from the user's type and roles when the token was issued.
+    #[serde(default)]
+    pub permissions: Vec<Permission>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -25,7 +36,8 @@ pub struct UserMinInfo {
     pub user_type: UserType,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+/// A stored user including the password hash. Deliberately not `Serialize`, respond with [`UserPublic`] or [`UserAdminView`].
+#[derive(Debug, Clone, PartialEq, FromRow)]
 pub struct UserDetail {
     pub id: Uuid,
     pub user_type: UserType,
@@ -35,9 +47,16 @@ pub struct UserDetail {
     pub email: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Access tokens issued at or before this time are rejected.
+    pub tokens_valid_after: Option<DateTime<Utc>>,
+    pub is_deleted: bool,
+    /// When the user was deleted, `None` for live users.
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Incremented by every change, the `ETag` of the user.
+    pub version: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
 pub struct UserDetailToAddOrUpdate {
     pub alias: String,
     pub username: String,
@@ -45,6 +64,168 @@ pub struct UserDetailToAddOrUpdate {
     pub email: String,
 }
 
+/// Replaces the fields of a user. The password is changed only through its own endpoint, which signs the user out.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct UserDetailToUpdate {
+    pub alias: String,
+    pub username: String,
+    pub email: String,
+}
+
+/// A user as seen by anyone allowed to list users, without contact details or secrets.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct UserPublic {
+    pub id: Uuid,
+    pub user_type: UserType,
+    pub alias: String,
+    pub username: String,
+    pub created_at: DateTime<Utc>,
+    /// Only ever `true` in lists that include deleted users.
+    pub is_deleted: bool,
+}
+
+/// A user as seen by themselves or by someone holding `users.read`. Still without the password hash.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct UserAdminView {
+    pub id: Uuid,
+    pub user_type: UserType,
+    pub alias: String,
+    pub username: String,
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Access tokens issued at or before this time are rejected.
+    pub tokens_valid_after: Option<DateTime<Utc>>,
+    pub is_deleted: bool,
+    /// When the user was deleted, the retention worker purges them once this is older than `retention.deleted_user_days`.
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Incremented by every change. Send it back in `If-Match` to update only the version you read.
+    pub version: i64,
+}
+
+/// Either view of a user, depending on what the caller may read.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(untagged)]
+pub enum UserView {
+    Admin(UserAdminView),
+    Public(UserPublic),
+}
+
+/// Columns users can be sorted by, any other is rejected.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSortKey {
+    /// Creation order, as IDs are UUIDv7. The only key cursor mode supports.
+    #[default]
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Username,
+    Alias,
+}
+
+impl UserSortKey {
+    pub fn column(self) -> &'static str {
+        match self {
+            UserSortKey::Id => "id",
+            UserSortKey::CreatedAt => "created_at",
+            UserSortKey::UpdatedAt => "updated_at",
+            UserSortKey::Username => "username",
+            UserSortKey::Alias => "alias",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserSort {
+    /// Column to sort by, ties are broken by ID.
+    #[serde(default)]
+    pub sort: UserSortKey,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+/// Narrows a user list, every given condition must match.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserFilter {
+    /// Only users whose username contains this, ignoring case.
+    pub username: Option<String>,
+    /// Only users whose alias contains this, ignoring case.
+    pub alias: Option<String>,
+    /// Only users whose email contains this, ignoring case.
+    pub email: Option<String>,
+    pub user_type: Option<UserType>,
+    /// Only users created at or after this time.
+    pub created_since: Option<DateTime<Utc>>,
+    /// Only users created before this time.
+    pub created_before: Option<DateTime<Utc>>,
+    /// Whether to list deleted users, anything but `exclude` requires `users.delete`.
+    #[serde(default)]
+    pub deleted: DeletedUsers,
+}
+
+/// How a user list treats deleted users.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletedUsers {
+    #[default]
+    Exclude,
+    Include,
+    /// Only deleted users.
+    Only,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserSearchQuery {
+    /// Text to find in alias, username or email. Words of three or more characters are matched anywhere, all of them must match.
+    pub q: String,
+    /// Most results to return, 20 if left out.
+    pub limit: Option<i64>,
+}
+
+/// A user matching a search.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct UserSearchHit<T> {
+    pub user: T,
+    /// Relevance, higher is better. Only comparable within one search.
+    pub score: f64,
+    /// The best matching field with the matches wrapped in `<mark>` and `</mark>`. The rest is returned as stored, not HTML-escaped.
+    pub snippet: String,
+}
+
+impl<T> UserSearchHit<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> UserSearchHit<U> {
+        UserSearchHit {
+            user: f(self.user),
+            score: self.score,
+            snippet: self.snippet,
+        }
+    }
+}
+
+/// Changes to a user, fields left out keep their value. Password and email have their own endpoints.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UserPatch {
+    pub alias: Option<String>,
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PasswordChange {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct EmailChange {
+    /// The new email address, empty to remove it.
+    pub email: String,
+}
+
 impl UserSummary {
     pub fn is_admin(&self) -> bool {
         matches!(self.user_type, UserType::Admin)
@@ -57,6 +238,60 @@ impl UserSummary {
     pub fn is_regular(&self) -> bool {
         matches!(self.user_type, UserType::Regular)
     }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+
+    /// When the token was issued, taken from `jti` and falling back to the whole-second `iat`.
+    pub fn issued_at(&self) -> DateTime<Utc> {
+        self.jti
+            .get_timestamp()
+            .and_then(|timestamp| {
+                let (secs, nanos) = timestamp.to_unix();
+                DateTime::from_timestamp(secs as i64, nanos)
+            })
+            .or_else(|| DateTime::from_timestamp(self.iat as i64, 0))
+            .unwrap_or_default()
+    }
+}
+
+impl From<UserDetail> for UserPublic {
+    fn from(value: UserDetail) -> Self {
+        Self {
+            id: value.id,
+            user_type: value.user_type,
+            alias: value.alias,
+            username: value.username,
+            created_at: value.created_at,
+            is_deleted: value.is_deleted,
+        }
+    }
+}
+
+impl From<UserDetail> for UserAdminView {
+    fn from(value: UserDetail) -> Self {
+        Self {
+            id: value.id,
+            user_type: value.user_type,
+            alias: value.alias,
+            username: value.username,
+            email: value.email,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            tokens_valid_after: value.tokens_valid_after,
+            is_deleted: value.is_deleted,
+            deleted_at: value.deleted_at,
+            version: value.version,
+        }
+    }
+}
+
+impl UserView {
+    /// The admin view if `detailed`, the public one otherwise.
+    pub fn new(detail: UserDetail, detailed: bool) -> Self {
+        if detailed { Self::Admin(detail.into()) } else { Self::Public(detail.into()) }
+    }
 }
 
 impl From<UserMinInfo> for Uuid {
@@ -0,0 +1,15 @@
+use shared::{config::ConfigLoader, error::CommonError};
+
+const MISSING: &str = "./does-not-exist/config.toml";
+
+#[test]
+fn explicit_missing_file_fails() {
+    let result = ConfigLoader::new().file(MISSING).load_with_env([]);
+    assert!(matches!(result, Err(CommonError::NotFound { path }) if path.ends_with("config.toml")));
+}
+
+#[test]
+fn default_missing_file_is_skipped() {
+    let loaded = ConfigLoader::new().default_file(MISSING).load_with_env([]).unwrap();
+    assert!(loaded.file.is_none());
+}
@@ -0,0 +1,60 @@
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::Value;
+use shared::models::{
+    Page, Pagination,
+    user::{UserAdminView, UserDetail, UserPublic, UserSearchHit, UserType, UserView},
+};
+use uuid::Uuid;
+
+const HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$c2FsdA$aGFzaA";
+
+fn detail() -> UserDetail {
+    UserDetail {
+        id: Uuid::now_v7(),
+        user_type: UserType::Regular,
+        alias: "Alice".to_owned(),
+        username: "alice".to_owned(),
+        password: HASH.to_owned(),
+        email: "alice@example.com".to_owned(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        tokens_valid_after: Some(Utc::now()),
+        is_deleted: true,
+        deleted_at: Some(Utc::now()),
+        version: 1,
+    }
+}
+
+fn has_password(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => map.contains_key("password") || map.values().any(has_password),
+        Value::Array(items) => items.iter().any(has_password),
+        Value::String(s) => s == HASH,
+        _ => false,
+    }
+}
+
+#[track_caller]
+fn assert_no_password(response: impl Serialize) {
+    let json = serde_json::to_value(response).unwrap();
+    assert!(!has_password(&json), "{json}");
+}
+
+/// Every type a route responds with a user in.
+#[test]
+fn user_responses_leave_out_the_password() {
+    assert_no_password(UserPublic::from(detail()));
+    assert_no_password(UserAdminView::from(detail()));
+    assert_no_password(UserView::new(detail(), false));
+    assert_no_password(UserView::new(detail(), true));
+    assert_no_password(Some(UserAdminView::from(detail())));
+
+    let hit = UserSearchHit { user: detail(), score: 1.0, snippet: "[alice]".to_owned() };
+    assert_no_password(vec![hit.map(UserAdminView::from)]);
+
+    for detailed in [false, true] {
+        let page = Page::new(vec![detail(), detail()], 2, &Pagination::new(1, 10), |user| Some(user.id));
+        assert_no_password(page.map(|detail| UserView::new(detail, detailed)));
+    }
+}
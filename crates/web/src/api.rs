@@ -1,10 +1,11 @@
-mod api_result;
-mod login_auth;
+pub(crate) mod api_result;
+pub(crate) mod etag;
+pub(crate) mod login_auth;
 
 use crate::{
     api::{
         api_result::ApiResult,
-        login_auth::{LoginAuthRequest, LoginAuthResponse, LoginFailure, ReasonField},
+        login_auth::{BootstrapRequest, LoginAuthRequest, LoginAuthResponse, LoginFailure, LogoutRequest, ReasonField, RefreshTokenRequest},
     },
     app_state::AppState,
     jwt,
@@ -12,56 +13,294 @@ use crate::{
 };
 use api_result::Result;
 use axum::{
-    Json, Router, debug_handler,
+    Json, debug_handler,
     extract::{Path, Query, State},
-    routing,
+    http::{HeaderMap, header},
 };
 
-use service::service_ext::user_ext::UserExt;
+use service::service_ext::{permission_ext::PermissionExt, token_ext::TokenExt, user_ext::UserExt};
 use shared::models::{
-    Pagination,
-    user::{UserDetail, UserDetailToAddOrUpdate},
+    Page, Pagination,
+    permission::{Permission, Role, RoleToAddOrUpdate},
+    user::{DeletedUsers, EmailChange, PasswordChange, UserAdminView, UserDetailToAddOrUpdate, UserDetailToUpdate, UserFilter, UserPatch, UserSearchHit, UserSearchQuery, UserSort, UserView},
 };
+use utoipa_axum::{router::OpenApiRouter, routes};
 use uuid::Uuid;
 
-pub fn router() -> Router<AppState> {
-    Router::new()
-        .route("/users", routing::post(add_user).get(get_user_list))
-        .route("/users/{id}", routing::delete(remove_user).get(get_user).put(update_user))
-        .route("/login", routing::post(login))
+pub const USERS_TAG: &str = "users";
+pub const ROLES_TAG: &str = "roles";
+pub const AUTH_TAG: &str = "auth";
+
+pub fn router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(add_user, get_user_list))
+        .routes(routes!(search_users))
+        .routes(routes!(get_deleted_user_list))
+        .routes(routes!(purge_user))
+        .routes(routes!(restore_user))
+        .routes(routes!(remove_user, get_user, update_user, patch_user))
+        .routes(routes!(change_user_password))
+        .routes(routes!(change_user_email))
+        .routes(routes!(get_user_roles))
+        .routes(routes!(assign_role, unassign_role))
+        .routes(routes!(add_role, get_role_list))
+        .routes(routes!(remove_role, get_role, update_role))
+        .routes(routes!(bootstrap))
+        .routes(routes!(login))
+        .routes(routes!(refresh_token))
+        .routes(routes!(logout))
+        .routes(routes!(logout_all))
 }
 
+#[utoipa::path(post, path = "/users", tag = USERS_TAG, summary = "Register a user",
+    description = "Creates a regular user. Open to anonymous callers unless `security.allow_registration` is disabled, then it requires `users.create`.",
+    request_body = UserDetailToAddOrUpdate,
+    responses((status = OK, description = "ID of the new user.", body = ApiResult<Uuid>)),
+    security((), ("bearer_auth" = [])))]
 #[debug_handler]
 async fn add_user(State(app): State<AppState>, CurrentUser(user): CurrentUser, Json(detail): Json<UserDetailToAddOrUpdate>) -> Result<Uuid> {
     ApiResult::ok(app.core(user).add_user(detail).await?)
 }
 
+#[utoipa::path(delete, path = "/users/{id}", tag = USERS_TAG, summary = "Delete a user",
+    description = "Requires `users.delete`.",
+    params(("id" = Uuid, Path, description = "User ID.")),
+    responses((status = OK, description = "Whether a user was deleted.", body = ApiResult<bool>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
 #[debug_handler]
 async fn remove_user(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path(id): Path<Uuid>) -> Result<bool> {
     ApiResult::ok(app.core(user).remove_user(id).await?)
 }
 
+#[utoipa::path(get, path = "/users/{id}", tag = USERS_TAG, summary = "Get a user",
+    description = "Requires `users.read` unless reading yourself. Pass the `ETag` back in `If-Match` when updating the user.",
+    params(("id" = Uuid, Path, description = "User ID.")),
+    responses((status = OK, description = "The user, `null` if it does not exist.", body = ApiResult<Option<UserAdminView>>, headers(("ETag" = String, description = "Version of the user, only if it exists."))), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn get_user(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path(id): Path<Uuid>) -> Result<Option<UserAdminView>> {
+    let user = app.core(user).get_user(id).await?.map(UserAdminView::from);
+    let version = user.as_ref().map(|user| user.version);
+    let result = ApiResult::ok(user)?;
+    Ok(match version {
+        Some(version) => result.with_header(header::ETAG, etag::etag(version)),
+        None => result,
+    })
+}
+
+#[utoipa::path(get, path = "/users", tag = USERS_TAG, summary = "List users",
+    description = "Requires `users.list`. Email and timestamps other than `created_at` are only included with `users.read`. Pages by number, or with `after` by cursor, which requires sorting by `id`. Listing deleted users requires `users.delete`.",
+    params(Pagination, UserSort, UserFilter),
+    responses((status = OK, description = "One page of users.", body = ApiResult<Page<UserView>>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn get_user_list(State(app): State<AppState>, CurrentUser(user): CurrentUser, Query(pagination): Query<Pagination>, Query(sort): Query<UserSort>, Query(filter): Query<UserFilter>) -> Result<Page<UserView>> {
+    let detailed = user.as_ref().is_some_and(|user| user.has_permission(Permission::UserRead));
+    let page = app.core(user).get_user_list(pagination, sort, filter).await?;
+    ApiResult::ok(page.map(|detail| UserView::new(detail, detailed)))
+}
+
+#[utoipa::path(get, path = "/users/search", tag = USERS_TAG, summary = "Search users",
+    description = "Requires `users.list` and `users.read`. Matches alias, username and email, best matches first. `q` must be 3 to 128 characters long, `limit` at most 100.",
+    params(UserSearchQuery),
+    responses((status = OK, description = "Matching users.", body = ApiResult<Vec<UserSearchHit<UserAdminView>>>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn search_users(State(app): State<AppState>, CurrentUser(user): CurrentUser, Query(query): Query<UserSearchQuery>) -> Result<Vec<UserSearchHit<UserAdminView>>> {
+    let hits = app.core(user).search_users(&query.q, query.limit).await?;
+    ApiResult::ok(hits.into_iter().map(|hit| hit.map(UserAdminView::from)).collect())
+}
+
+#[utoipa::path(get, path = "/users/deleted", tag = USERS_TAG, summary = "List deleted users",
+    description = "Requires `users.list` and `users.delete`. Deleted users can be restored until the retention worker purges them, `retention.deleted_user_days` after `deleted_at`.",
+    params(Pagination, UserSort),
+    responses((status = OK, description = "One page of deleted users.", body = ApiResult<Page<UserView>>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn get_deleted_user_list(State(app): State<AppState>, CurrentUser(user): CurrentUser, Query(pagination): Query<Pagination>, Query(sort): Query<UserSort>) -> Result<Page<UserView>> {
+    let detailed = user.as_ref().is_some_and(|user| user.has_permission(Permission::UserRead));
+    let filter = UserFilter {
+        deleted: DeletedUsers::Only,
+        ..UserFilter::default()
+    };
+    let page = app.core(user).get_user_list(pagination, sort, filter).await?;
+    ApiResult::ok(page.map(|detail| UserView::new(detail, detailed)))
+}
+
+#[utoipa::path(post, path = "/users/deleted/{id}/restore", tag = USERS_TAG, summary = "Restore a deleted user",
+    description = "Requires `users.delete`. Fails with `Conflict` if a live user took the username or email meanwhile.",
+    params(("id" = Uuid, Path, description = "User ID.")),
+    responses((status = OK, description = "Whether a deleted user was restored.", body = ApiResult<bool>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn restore_user(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path(id): Path<Uuid>) -> Result<bool> {
+    ApiResult::ok(app.core(user).restore_user(id).await?)
+}
+
+#[utoipa::path(delete, path = "/users/deleted/{id}", tag = USERS_TAG, summary = "Purge a deleted user",
+    description = "Requires `users.delete`. Permanently deletes the user with their sessions and role assignments, this can't be undone. Live users must be deleted first.",
+    params(("id" = Uuid, Path, description = "User ID.")),
+    responses((status = OK, description = "Whether a deleted user was purged.", body = ApiResult<bool>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn purge_user(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path(id): Path<Uuid>) -> Result<bool> {
+    ApiResult::ok(app.core(user).purge_user(id).await?)
+}
+
+#[utoipa::path(put, path = "/users/{id}", tag = USERS_TAG, summary = "Update a user",
+    description = "Requires `users.update` unless updating yourself. With `If-Match` the user is only updated if nobody changed it since it was read. The password is changed through its own endpoint.",
+    params(("id" = Uuid, Path, description = "User ID."), ("If-Match" = Option<String>, Header, description = "`ETag` of the user as last read, or `*` for any version.")),
+    request_body = UserDetailToUpdate,
+    responses(
+        (status = OK, description = "Whether a user was updated.", body = ApiResult<bool>),
+        (status = UNAUTHORIZED, description = "Invalid, expired or revoked token."),
+        (status = PRECONDITION_FAILED, description = "The user was changed since it was read, or `If-Match` is malformed.", body = ApiResult<bool>)),
+    security(("bearer_auth" = [])))]
 #[debug_handler]
-async fn get_user(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path(id): Path<Uuid>) -> Result<Option<UserDetail>> {
-    ApiResult::ok(app.core(user).get_user(id).await?)
+async fn update_user(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path(id): Path<Uuid>, headers: HeaderMap, Json(detail): Json<UserDetailToUpdate>) -> Result<bool> {
+    let version = etag::if_match(&headers)?;
+    ApiResult::ok(app.core(user).update_user(id, detail, version).await?)
 }
 
+#[utoipa::path(patch, path = "/users/{id}", tag = USERS_TAG, summary = "Partially update a user",
+    description = "Requires `users.update` unless updating yourself. Fields left out keep their value, change the password and email through their own endpoints.",
+    params(("id" = Uuid, Path, description = "User ID.")),
+    request_body = UserPatch,
+    responses((status = OK, description = "Whether a user was updated.", body = ApiResult<bool>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
 #[debug_handler]
-async fn get_user_list(State(app): State<AppState>, CurrentUser(user): CurrentUser, Query(pagination): Query<Pagination>) -> Result<Vec<UserDetail>> {
-    ApiResult::ok(app.core(user).get_user_list(pagination).await?)
+async fn patch_user(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path(id): Path<Uuid>, Json(patch): Json<UserPatch>) -> Result<bool> {
+    ApiResult::ok(app.core(user).patch_user(id, patch).await?)
 }
 
+#[utoipa::path(put, path = "/users/{id}/password", tag = USERS_TAG, summary = "Change the password of a user",
+    description = "Requires `users.update` unless changing your own, and the user's current password either way. Every session of the user is signed out, including the calling one.",
+    params(("id" = Uuid, Path, description = "User ID.")),
+    request_body = PasswordChange,
+    responses((status = OK, description = "Whether the password was changed.", body = ApiResult<bool>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
 #[debug_handler]
-async fn update_user(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path(id): Path<Uuid>, Json(detail): Json<UserDetailToAddOrUpdate>) -> Result<bool> {
-    ApiResult::ok(app.core(user).update_user(id, detail).await?)
+async fn change_user_password(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path(id): Path<Uuid>, Json(change): Json<PasswordChange>) -> Result<bool> {
+    ApiResult::ok(app.core(user).change_user_password(id, &change.current_password, &change.new_password).await?)
 }
 
+#[utoipa::path(put, path = "/users/{id}/email", tag = USERS_TAG, summary = "Change the email of a user",
+    description = "Requires `users.update` unless changing your own.",
+    params(("id" = Uuid, Path, description = "User ID.")),
+    request_body = EmailChange,
+    responses((status = OK, description = "Whether the email was changed.", body = ApiResult<bool>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn change_user_email(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path(id): Path<Uuid>, Json(change): Json<EmailChange>) -> Result<bool> {
+    ApiResult::ok(app.core(user).set_user_email(id, &change.email).await?)
+}
+
+#[utoipa::path(get, path = "/users/{id}/roles", tag = ROLES_TAG, summary = "List the roles of a user",
+    description = "Requires `roles.read` unless reading yourself.",
+    params(("id" = Uuid, Path, description = "User ID.")),
+    responses((status = OK, description = "Roles assigned to the user.", body = ApiResult<Vec<Role>>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn get_user_roles(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path(id): Path<Uuid>) -> Result<Vec<Role>> {
+    ApiResult::ok(app.core(user).get_user_roles(id).await?)
+}
+
+#[utoipa::path(put, path = "/users/{id}/roles/{role_id}", tag = ROLES_TAG, summary = "Assign a role to a user",
+    description = "Requires `roles.assign`. The user's current access tokens expire and must be refreshed.",
+    params(("id" = Uuid, Path, description = "User ID."), ("role_id" = Uuid, Path, description = "Role ID.")),
+    responses((status = OK, description = "Whether the role was newly assigned, `false` if the user or the role doesn't exist.", body = ApiResult<bool>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn assign_role(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path((id, role_id)): Path<(Uuid, Uuid)>) -> Result<bool> {
+    ApiResult::ok(app.core(user).assign_role(id, role_id).await?)
+}
+
+#[utoipa::path(delete, path = "/users/{id}/roles/{role_id}", tag = ROLES_TAG, summary = "Remove a role from a user",
+    description = "Requires `roles.assign`. The user's current access tokens expire and must be refreshed.",
+    params(("id" = Uuid, Path, description = "User ID."), ("role_id" = Uuid, Path, description = "Role ID.")),
+    responses((status = OK, description = "Whether the role was removed.", body = ApiResult<bool>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn unassign_role(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path((id, role_id)): Path<(Uuid, Uuid)>) -> Result<bool> {
+    ApiResult::ok(app.core(user).unassign_role(id, role_id).await?)
+}
+
+#[utoipa::path(post, path = "/roles", tag = ROLES_TAG, summary = "Create a role",
+    description = "Requires `roles.manage`.",
+    request_body = RoleToAddOrUpdate,
+    responses((status = OK, description = "ID of the new role.", body = ApiResult<Uuid>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn add_role(State(app): State<AppState>, CurrentUser(user): CurrentUser, Json(role): Json<RoleToAddOrUpdate>) -> Result<Uuid> {
+    ApiResult::ok(app.core(user).add_role(role).await?)
+}
+
+#[utoipa::path(delete, path = "/roles/{id}", tag = ROLES_TAG, summary = "Delete a role",
+    description = "Requires `roles.manage`. Access tokens of every user holding the role expire.",
+    params(("id" = Uuid, Path, description = "Role ID.")),
+    responses((status = OK, description = "Whether a role was deleted.", body = ApiResult<bool>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn remove_role(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path(id): Path<Uuid>) -> Result<bool> {
+    ApiResult::ok(app.core(user).remove_role(id).await?)
+}
+
+#[utoipa::path(get, path = "/roles/{id}", tag = ROLES_TAG, summary = "Get a role",
+    description = "Requires `roles.read`.",
+    params(("id" = Uuid, Path, description = "Role ID.")),
+    responses((status = OK, description = "The role, `null` if it does not exist.", body = ApiResult<Option<Role>>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn get_role(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path(id): Path<Uuid>) -> Result<Option<Role>> {
+    ApiResult::ok(app.core(user).get_role(id).await?)
+}
+
+#[utoipa::path(get, path = "/roles", tag = ROLES_TAG, summary = "List roles",
+    description = "Requires `roles.read`.",
+    responses((status = OK, description = "Every role.", body = ApiResult<Vec<Role>>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn get_role_list(State(app): State<AppState>, CurrentUser(user): CurrentUser) -> Result<Vec<Role>> {
+    ApiResult::ok(app.core(user).get_role_list().await?)
+}
+
+#[utoipa::path(put, path = "/roles/{id}", tag = ROLES_TAG, summary = "Update a role",
+    description = "Requires `roles.manage`. Access tokens of every user holding the role expire.",
+    params(("id" = Uuid, Path, description = "Role ID.")),
+    request_body = RoleToAddOrUpdate,
+    responses((status = OK, description = "Whether a role was updated.", body = ApiResult<bool>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn update_role(State(app): State<AppState>, CurrentUser(user): CurrentUser, Path(id): Path<Uuid>, Json(role): Json<RoleToAddOrUpdate>) -> Result<bool> {
+    ApiResult::ok(app.core(user).update_role(id, role).await?)
+}
+
+#[utoipa::path(post, path = "/bootstrap", tag = AUTH_TAG, summary = "Create the first admin",
+    description = "Requires the bootstrap token from `security.bootstrap_token` or the startup log. Fails once any admin exists or was bootstrapped.",
+    request_body = BootstrapRequest,
+    responses((status = OK, description = "ID of the new admin.", body = ApiResult<Uuid>)))]
+#[debug_handler]
+async fn bootstrap(State(app): State<AppState>, Json(request): Json<BootstrapRequest>) -> Result<Uuid> {
+    ApiResult::ok(app.core(None).bootstrap_admin(&request.token, request.user).await?)
+}
+
+#[utoipa::path(post, path = "/login", tag = AUTH_TAG, summary = "Log in",
+    description = "On wrong credentials `status` is `Fail` and `data` is a `LoginFailure`.",
+    request_body = LoginAuthRequest,
+    responses((status = OK, description = "Access and refresh token of the user.", body = ApiResult<LoginAuthResponse>)))]
 #[debug_handler]
 async fn login(State(app): State<AppState>, Json(credentials): Json<LoginAuthRequest>) -> Result {
     let user = app.core(None).get_user_by_validate(&credentials.username, &credentials.password).await?;
     if let Some(user) = user {
-        let token = jwt::generate_token(&user, app.com.config().security.auth_key.as_str())?;
-        let response = LoginAuthResponse { user_id: user.id, token };
+        let security = &app.com.config().security;
+        let permissions = app.core(None).resolve_permissions(&user).await?;
+        let token = jwt::generate_token(&user, permissions, security.auth_key.as_str(), security.access_token_ttl_secs)?;
+        let refresh_token = app.core(None).issue_refresh_token(user.id).await?;
+        let response = LoginAuthResponse {
+            user_id: user.id,
+            token,
+            refresh_token,
+        };
         if let Ok(response) = serde_json::to_value(&response) {
             ApiResult::ok(response)
         } else {
@@ -71,7 +310,9 @@ async fn login(State(app): State<AppState>, Json(credentials): Json<LoginAuthReq
         let response = LoginFailure {
             reasons: vec![ReasonField {
                 field: "username/password".to_string(),
+                code: None,
                 message: "Invalid username or password.".to_string(),
+                params: Default::default(),
             }],
         };
 
@@ -82,3 +323,42 @@ async fn login(State(app): State<AppState>, Json(credentials): Json<LoginAuthReq
         }
     }
 }
+
+#[utoipa::path(post, path = "/token/refresh", tag = AUTH_TAG, summary = "Refresh an access token",
+    description = "Consumes the refresh token and returns a new pair. Presenting a used refresh token revokes its whole family.",
+    request_body = RefreshTokenRequest,
+    responses((status = OK, description = "New access and refresh token.", body = ApiResult<LoginAuthResponse>)))]
+#[debug_handler]
+async fn refresh_token(State(app): State<AppState>, Json(request): Json<RefreshTokenRequest>) -> Result<LoginAuthResponse> {
+    let (user, refresh_token) = app.core(None).rotate_refresh_token(&request.refresh_token).await?;
+    let security = &app.com.config().security;
+    let permissions = app.core(None).resolve_permissions(&user).await?;
+    let token = jwt::generate_token(&user, permissions, security.auth_key.as_str(), security.access_token_ttl_secs)?;
+    ApiResult::ok(LoginAuthResponse {
+        user_id: user.id,
+        token,
+        refresh_token,
+    })
+}
+
+#[utoipa::path(post, path = "/logout", tag = AUTH_TAG, summary = "Log out",
+    description = "Revokes the current access token, and the refresh token family if one is given.",
+    request_body(content = Option<LogoutRequest>),
+    responses((status = OK, description = "Always `true`.", body = ApiResult<bool>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn logout(State(app): State<AppState>, CurrentUser(user): CurrentUser, request: Option<Json<LogoutRequest>>) -> Result<bool> {
+    let Json(request) = request.unwrap_or_default();
+    app.core(user).logout(request.refresh_token.as_deref()).await?;
+    ApiResult::ok(true)
+}
+
+#[utoipa::path(post, path = "/logout/all", tag = AUTH_TAG, summary = "Log out everywhere",
+    description = "Revokes every access and refresh token of the current user.",
+    responses((status = OK, description = "Always `true`.", body = ApiResult<bool>), (status = UNAUTHORIZED, description = "Invalid, expired or revoked token.")),
+    security(("bearer_auth" = [])))]
+#[debug_handler]
+async fn logout_all(State(app): State<AppState>, CurrentUser(user): CurrentUser) -> Result<bool> {
+    app.core(user).logout_everywhere().await?;
+    ApiResult::ok(true)
+}
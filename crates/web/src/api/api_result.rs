@@ -1,10 +1,19 @@
-use axum::response::IntoResponse;
+use axum::{
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::IntoResponse,
+};
 use serde::Serialize;
+use utoipa::ToSchema;
 use tracing::error;
 
+use crate::api::login_auth::ReasonField;
+
 pub type Result<T = serde_json::Value> = std::result::Result<ApiResult<T>, ApiResult<T>>;
 
-#[derive(Debug, Serialize)]
+/// Envelope of every API response.
+///
+/// `Ok` carries `data`, `Fail` carries `data` describing a rejected request, and `Err` carries `error`.
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ApiResult<T>
 where
     T: Serialize,
@@ -14,40 +23,59 @@ where
     data: Option<T>,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<ApiResultError>,
+    /// HTTP status of the response. `200 OK` but where HTTP semantics demand another, e.g. `412` for a failed `If-Match`.
+    #[serde(skip)]
+    http_status: StatusCode,
+    /// Boxed, as every handler returns this as its error too.
+    #[serde(skip)]
+    headers: Option<Box<HeaderMap>>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub enum ApiStatus {
     Ok,
     Fail,
     Err,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ApiResultError {
     #[serde(skip_serializing_if = "Option::is_none")]
     msg: Option<String>,
     code: ErrorCode,
+    /// Every rejected field, for `ValidationError`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasons: Option<Vec<ReasonField>>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 #[repr(u16)]
+#[allow(clippy::enum_variant_names)]
 pub enum ErrorCode {
     CommonError,
     StorageError,
     AuthError,
+    /// Not produced by this server, kept so clients matching on the published codes keep working.
+    #[allow(dead_code)]
     FlexiError,
     JWTError,
     InternalError,
     FormatError,
+    /// A unique value such as a username or email is already taken.
+    Conflict,
+    /// Fields of the request are invalid, see `reasons`.
+    ValidationError,
+    /// The record no longer matches `If-Match`, read it again and retry.
+    PreconditionFailed,
 }
 
 impl<T> IntoResponse for ApiResult<T>
 where
     T: Serialize,
 {
-    fn into_response(self) -> axum::response::Response {
-        axum::Json(self).into_response()
+    fn into_response(mut self) -> axum::response::Response {
+        let headers = self.headers.take().map(|headers| *headers).unwrap_or_default();
+        (self.http_status, headers, axum::Json(self)).into_response()
     }
 }
 
@@ -56,17 +84,32 @@ where
     T: Serialize,
 {
     fn from(value: service::Error) -> Self {
+        let mut reasons = None;
+        let mut http_status = StatusCode::OK;
         let (msg, code) = match value {
             service::Error::Common(common_error) => (common_error.to_string(), ErrorCode::CommonError),
             service::Error::StorageError(error) => (error.to_string(), ErrorCode::StorageError),
             service::Error::AuthError(error) => (error.to_string(), ErrorCode::AuthError),
+            service::Error::PermissionDenied(permission) => (format!("Permission denied, missing `{permission}`."), ErrorCode::AuthError),
             service::Error::FormatError(error) => (error.to_string(), ErrorCode::FormatError),
+            service::Error::Validation(errors) => {
+                reasons = Some(errors.iter().map(ReasonField::from).collect());
+                ("Invalid request, see `reasons`.".to_string(), ErrorCode::ValidationError)
+            }
+            service::Error::PasswordHashError(error) => (error.to_string(), ErrorCode::InternalError),
+            service::Error::Conflict(field) => (format!("`{field}` is already taken."), ErrorCode::Conflict),
+            service::Error::PreconditionFailed(version) => {
+                http_status = StatusCode::PRECONDITION_FAILED;
+                (format!("The record was changed, it is no longer at version {version}."), ErrorCode::PreconditionFailed)
+            }
         };
         error!("Service error ({code:?}): {msg}");
         Self {
             status: ApiStatus::Err,
             data: None,
-            error: Some(ApiResultError { msg: Some(msg), code }),
+            error: Some(ApiResultError { msg: Some(msg), code, reasons }),
+            http_status,
+            headers: None,
         }
     }
 }
@@ -80,7 +123,9 @@ where
         Self {
             status: ApiStatus::Err,
             data: None,
-            error: Some(ApiResultError { msg: Some(msg), code }),
+            error: Some(ApiResultError { msg: Some(msg), code, reasons: None }),
+            http_status: StatusCode::OK,
+            headers: None,
         }
     }
 }
@@ -94,6 +139,8 @@ where
             status: ApiStatus::Ok,
             data: Some(data),
             error: None,
+            http_status: StatusCode::OK,
+            headers: None,
         })
     }
     pub fn fail(data: T) -> std::result::Result<Self, Self> {
@@ -101,6 +148,8 @@ where
             status: ApiStatus::Fail,
             data: Some(data),
             error: None,
+            http_status: StatusCode::OK,
+            headers: None,
         })
     }
     pub fn error(content: &str) -> std::result::Result<Self, Self> {
@@ -110,7 +159,28 @@ where
             error: Some(ApiResultError {
                 msg: Some(content.to_string()),
                 code: ErrorCode::InternalError,
+                reasons: None,
             }),
+            http_status: StatusCode::OK,
+            headers: None,
         })
     }
+    /// A `412 Precondition Failed` for a request whose `If-Match` can never match.
+    pub fn precondition_failed(content: &str) -> Self {
+        Self {
+            status: ApiStatus::Err,
+            data: None,
+            error: Some(ApiResultError {
+                msg: Some(content.to_string()),
+                code: ErrorCode::PreconditionFailed,
+                reasons: None,
+            }),
+            http_status: StatusCode::PRECONDITION_FAILED,
+            headers: None,
+        }
+    }
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.get_or_insert_default().insert(name, value);
+        self
+    }
 }
@@ -0,0 +1,29 @@
+use axum::http::{HeaderMap, HeaderValue, header};
+use serde::Serialize;
+
+use crate::api::api_result::ApiResult;
+
+/// Strong entity tag of a record at `version`.
+pub(crate) fn etag(version: i64) -> HeaderValue {
+    HeaderValue::try_from(format!("\"{version}\"")).expect("a quoted number is a valid header value")
+}
+
+/// The version `If-Match` requires, `None` without the header or for `*`.
+///
+/// Only a single strong ETag as returned by [`etag`] is understood. Weak or unknown tags can never match a strong
+/// comparison, so they fail right away, as do lists of tags.
+pub(crate) fn if_match<T: Serialize>(headers: &HeaderMap) -> Result<Option<i64>, ApiResult<T>> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+    let value = value.to_str().unwrap_or_default().trim();
+    if value == "*" {
+        return Ok(None);
+    }
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .and_then(|version| version.parse().ok())
+        .map(Some)
+        .ok_or_else(|| ApiResult::precondition_failed("`If-Match` must be `*` or a single ETag as returned when reading the record."))
+}
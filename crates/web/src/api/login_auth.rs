@@ -1,24 +1,62 @@
+use shared::models::user::UserDetailToAddOrUpdate;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize, ToSchema)]
 pub struct LoginAuthRequest {
     pub username: String,
     pub password: String,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize, ToSchema)]
 pub struct LoginAuthResponse {
     pub user_id: Uuid,
     pub token: String,
+    pub refresh_token: String,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, ToSchema)]
 pub struct LoginFailure {
     pub reasons: Vec<ReasonField>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, ToSchema)]
 pub struct ReasonField {
     pub field: String,
+    /// Machine-readable reason, e.g. `too_short`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
     pub message: String,
+    /// Values the message refers to, e.g. `min` for `too_short`.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    #[schema(value_type = Object)]
+    pub params: serde_json::Map<String, serde_json::Value>,
+}
+
+impl From<&service::validation::FieldError> for ReasonField {
+    fn from(error: &service::validation::FieldError) -> Self {
+        Self {
+            field: error.field.to_owned(),
+            code: Some(error.code.to_owned()),
+            message: error.message.clone(),
+            params: error.params.clone(),
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Default, ToSchema)]
+pub struct LogoutRequest {
+    pub refresh_token: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct BootstrapRequest {
+    /// `security.bootstrap_token`, or the one-time token logged at startup.
+    pub token: String,
+    pub user: UserDetailToAddOrUpdate,
 }
@@ -1,17 +1,24 @@
 use chrono::{Duration, Utc};
 use jsonwebtoken::{EncodingKey, Header, encode};
-use shared::models::user::{UserDetail, UserSummary};
+use shared::models::{
+    permission::Permission,
+    user::{UserDetail, UserSummary},
+};
+use uuid::Uuid;
 
-pub fn generate_token(user: &UserDetail, secret: &str) -> Result<String, jsonwebtoken::errors::Error> {
-    // Set expiration to 1 hour from now
-    let expiration = Utc::now() + Duration::hours(1);
+pub fn generate_token(user: &UserDetail, permissions: Vec<Permission>, secret: &str, ttl_secs: u64) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let expiration = now + Duration::seconds(ttl_secs as i64);
 
     let claims = UserSummary {
         id: user.id,
-        user_type: user.user_type.clone(),
+        user_type: user.user_type,
         alias: user.alias.clone(),
         username: user.username.clone(),
+        jti: Uuid::now_v7(),
+        iat: now.timestamp() as usize,
         exp: expiration.timestamp() as usize, // Convert DateTime to Unix timestamp
+        permissions,
     };
 
     let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes()))?;
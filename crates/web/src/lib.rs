@@ -2,38 +2,46 @@ mod api;
 mod app_state;
 mod jwt;
 mod models;
+mod openapi;
 
 use axum::Router;
 use service::CommonService;
-use shared::config::Config;
+use shared::config::ServerConfig;
 use tower_http::services::ServeDir;
 use tower_http::services::ServeFile;
 use tracing::{error, info};
 
 use crate::app_state::AppState;
 
-fn root() -> Router<AppState> {
+pub use openapi::openapi;
+
+fn root(server: &ServerConfig) -> Router<AppState> {
     let static_files = ServeDir::new("./web").fallback(ServeDir::new("./web").append_index_html_on_directories(true).not_found_service(ServeFile::new("./web/index.html")));
-    Router::new().nest("/api", api::router()).fallback_service(static_files)
+    openapi::router(server.openapi_ui).fallback_service(static_files)
 }
 
-pub async fn serve(config: Config, com: CommonService) {
-    use tower_http::cors::{Any, CorsLayer};
+pub async fn serve(com: CommonService) {
+    use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 
+    // Host, port and routes are fixed at startup, only later reads of `com.config()` see reloads.
+    let config = com.config();
+    let cors_com = com.clone();
     let cors_layer = CorsLayer::new()
-        // Allow requests from any origin
-        .allow_origin(Any)
+        // Allow requests from the configured origins, read per request so a reload applies immediately
+        .allow_origin(AllowOrigin::predicate(move |origin, _| {
+            cors_com.config().server.cors_origins.iter().any(|allowed| allowed == "*" || origin.as_bytes() == allowed.as_bytes())
+        }))
         // Allow the standard methods (GET, POST, PUT, DELETE, etc.)
         .allow_methods(Any)
         // Allow any header
         .allow_headers(Any);
 
     let app = AppState { com };
-    let app = root().with_state(app).layer(cors_layer);
+    let app = root(&config.server).with_state(app).layer(cors_layer);
 
-    let server = config.server;
+    let server = &config.server;
     info!("Server will serve at {}:{}.", server.host, server.port);
-    let listener = tokio::net::TcpListener::bind((server.host, server.port)).await.unwrap();
+    let listener = tokio::net::TcpListener::bind((server.host.as_str(), server.port)).await.unwrap();
     match axum::serve(listener, app).with_graceful_shutdown(shutdown_signal()).await {
         Ok(()) => info!("Server exited."),
         Err(err) => error!("Server close unexpected: {err}"),
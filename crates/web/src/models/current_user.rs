@@ -2,7 +2,9 @@ use axum::extract::FromRequestParts;
 use axum::http::StatusCode;
 use axum::http::request::Parts;
 use jsonwebtoken::{DecodingKey, Validation, decode};
+use service::service_ext::token_ext::TokenExt;
 use shared::models::user::UserSummary;
+use tracing::error;
 
 use crate::app_state::AppState;
 
@@ -34,12 +36,17 @@ impl FromRequestParts<AppState> for CurrentUser {
                 Ok(token_data) => {
                     // 3. Token is valid, map claims to UserSummary
                     let claims = token_data.claims;
-                    // Return CurrentUser with Some(UserSummary)
-                    Ok(CurrentUser(Some(claims)))
-                }
-                Err(_) => {
-                    return Err((StatusCode::UNAUTHORIZED, "Invalid or Expired Token"));
+                    // 4. Reject tokens revoked on the server side since they were issued
+                    match state.core(None).is_access_token_valid(&claims).await {
+                        Ok(true) => Ok(CurrentUser(Some(claims))),
+                        Ok(false) => Err((StatusCode::UNAUTHORIZED, "Token has been revoked")),
+                        Err(err) => {
+                            error!("Cannot check token revocation: {err}");
+                            Err((StatusCode::INTERNAL_SERVER_ERROR, "Cannot verify token"))
+                        }
+                    }
                 }
+                Err(_) => Err((StatusCode::UNAUTHORIZED, "Invalid or Expired Token")),
             }
         } else {
             // 5. No Authorization header present - this is the "optional" part
@@ -0,0 +1,58 @@
+use axum::{Json, Router, routing};
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+};
+use utoipa_axum::router::OpenApiRouter;
+use utoipa_scalar::{Scalar, Servable};
+
+use crate::{
+    api::{
+        self, AUTH_TAG, ROLES_TAG, USERS_TAG,
+        api_result::{ApiStatus, ErrorCode},
+        login_auth::{LoginFailure, ReasonField},
+    },
+    app_state::AppState,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Template Web App API", description = "Every response is wrapped in an `ApiResult` envelope. Authenticate with the access token from `/api/login` as a bearer token."),
+    modifiers(&BearerAuth),
+    components(schemas(ApiStatus, ErrorCode, LoginFailure, ReasonField)),
+    tags(
+        (name = USERS_TAG, description = "User accounts."),
+        (name = ROLES_TAG, description = "Roles and the permissions they grant."),
+        (name = AUTH_TAG, description = "Login, token refresh and logout."),
+    )
+)]
+struct ApiDoc;
+
+/// Registers the JWT access token returned by `/api/login` as the `bearer_auth` scheme.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme("bearer_auth", SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()));
+    }
+}
+
+fn split_api() -> (Router<AppState>, utoipa::openapi::OpenApi) {
+    let mut doc = ApiDoc::openapi();
+    // The crate declares no license, don't advertise an empty one.
+    doc.info.license = None;
+    OpenApiRouter::with_openapi(doc).nest("/api", api::router()).split_for_parts()
+}
+
+/// OpenAPI document of every route under `/api`.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    split_api().1
+}
+
+/// Router of `/api` together with `/api/openapi.json` and, if `ui` is set, the API reference at `/api/docs`.
+pub(crate) fn router(ui: bool) -> Router<AppState> {
+    let (router, openapi) = split_api();
+    let router = router.route("/api/openapi.json", routing::get(Json(openapi.clone())));
+    if ui { router.merge(Scalar::with_url("/api/docs", openapi)) } else { router }
+}
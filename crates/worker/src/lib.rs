@@ -1,4 +1,5 @@
 pub mod error;
+pub mod retention;
 pub mod worker;
 
 use futures::future::join_all;
@@ -17,6 +18,12 @@ pub struct WorkerFactory {
     stop_notify: Arc<Notify>,
 }
 
+impl Default for WorkerFactory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl WorkerFactory {
     pub fn new() -> Self {
         Self {
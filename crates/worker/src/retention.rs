@@ -0,0 +1,48 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{TimeDelta, Utc};
+use db::db::FullDb;
+use shared::config::ConfigHandle;
+use tracing::{error, info};
+
+use crate::{Result, worker::Worker};
+
+/// Permanently deletes users once they have been deleted for longer than `retention.deleted_user_days`.
+pub struct DeletedUserRetention {
+    storage: Arc<dyn FullDb>,
+    config: ConfigHandle,
+}
+
+impl DeletedUserRetention {
+    pub fn new(storage: Arc<dyn FullDb>, config: ConfigHandle) -> Self {
+        Self { storage, config }
+    }
+}
+
+#[async_trait]
+impl Worker for DeletedUserRetention {
+    fn name(&self) -> &'static str {
+        "deleted-user-retention"
+    }
+
+    async fn loop_process(&self) -> Result<Duration> {
+        // Read on every run, so a reloaded configuration applies from the next one.
+        let retention = self.config.get().retention.clone();
+        let interval = Duration::from_secs(retention.interval_secs);
+        if retention.deleted_user_days == 0 {
+            return Ok(interval);
+        }
+        let Some(before) = Utc::now().checked_sub_signed(TimeDelta::days(retention.deleted_user_days.into())) else {
+            error!("Skipped the purge, {} days of retention reach before the earliest representable time.", retention.deleted_user_days);
+            return Ok(interval);
+        };
+        // An error would stop the worker for good, a failed run is retried on the next one instead.
+        match self.storage.purge_users_deleted_before(before).await {
+            Ok(0) => {}
+            Ok(purged) => info!("Purged {} user(s) deleted before {}.", purged, before),
+            Err(e) => error!("Failed to purge deleted users: {}", e),
+        }
+        Ok(interval)
+    }
+}